@@ -1,60 +1,148 @@
+use agent_skills_rs::cli::{
+    Cli, Commands, ErrorEnvelope, ErrorInfo, EventLine, InstallEvent, InstallSkillsOutput,
+    InstalledSkill, InstalledTarget, LockCommands, LockDoctorOutput, SchemaKind, ScopesOutput,
+    SyncOutput,
+};
+use agent_skills_rs::lock::compute_skill_hash;
 use agent_skills_rs::*;
-use anyhow::{Context, Result};
-use clap::{Parser, Subcommand};
-use directories::BaseDirs;
-use serde::{Deserialize, Serialize};
+use anyhow::Result;
+use clap::{CommandFactory, FromArgMatches};
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::io::{IsTerminal, Write};
 use std::path::{Path, PathBuf};
 
-#[derive(Parser)]
-#[command(name = "agent-skills-rs")]
-#[command(about = "A CLI tool with skill installation support", long_about = None)]
-struct Cli {
-    #[command(subcommand)]
-    command: Commands,
+const EXIT_CODES_HELP: &str = "\
+Exit codes:
+  0  Success
+  1  Internal or unexpected error
+  2  Usage error (invalid arguments, unknown agent, unknown command, invalid source or manifest, missing --yes)
+  3  Invalid skill definition or skill name, duplicate skill, or skill not found
+  4  Filesystem or install failure
+  5  Lock file is corrupt, invalid or doesn't match the installed source
+  6  Unsafe path rejected (path traversal, symlink escape)
+  7  Skill provider failure
+
+With --json (or --output json), failures are printed to stdout as
+{\"ok\": false, \"error\": {\"code\", \"message\", \"hint\", \"path\"}}.";
+
+/// Agent names accepted by `--agent` and the manifest's `agents`
+const KNOWN_AGENTS: &[&str] = &["claude", "opencode"];
+
+/// Errors raised by the CLI itself (as opposed to the library)
+#[derive(Debug, thiserror::Error)]
+enum CliError {
+    #[error("Unknown agent: '{0}'. Known agents: {}", KNOWN_AGENTS.join(", "))]
+    UnknownAgent(String),
+    #[error("{0} requires --yes; interactive confirmation is not available")]
+    ConfirmationRequired(&'static str),
+    #[error("--command is required unless --kind lock is given")]
+    MissingSchemaCommand,
+    #[error("--global can't be combined with --root or {}", scope::ROOT_ENV)]
+    ConflictingScope,
 }
 
-#[derive(Subcommand)]
-#[allow(clippy::enum_variant_names)]
-enum Commands {
-    /// List all available commands with JSON output
-    Commands {
-        #[arg(long, value_name = "FORMAT")]
-        output: Option<String>,
-    },
-    /// Get JSON schema for a command
-    Schema {
-        #[arg(long, value_name = "COMMAND")]
-        command: String,
-        #[arg(long, value_name = "FORMAT")]
-        output: Option<String>,
-    },
-    /// Install skills
-    InstallSkills {
-        /// Target agent name(s) for agent-specific installation (can be comma-separated or specified multiple times)
-        #[arg(long)]
-        agent: Vec<String>,
-        /// Specific skill name to install
-        #[arg(long)]
-        skill: Option<String>,
-        /// Install globally (default: project-local)
-        #[arg(long)]
-        global: bool,
-        /// Skip confirmation prompts
-        #[arg(long)]
-        yes: bool,
-        /// Run in non-interactive mode
-        #[arg(long)]
-        non_interactive: bool,
-        /// Output result as JSON
-        #[arg(long)]
-        json: bool,
-    },
+fn main() {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    let cli = Cli::command()
+        .after_help(EXIT_CODES_HELP)
+        .try_get_matches_from(&args)
+        .and_then(|matches| Cli::from_arg_matches(&matches))
+        .unwrap_or_else(|e| usage_error(e, &args));
+    let json_output = cli.command.json_output();
+    let events = cli.command.events_output();
+
+    if let Err(err) = run(cli) {
+        let info = error_info(&err);
+        if json_output {
+            match error_json(info.clone(), events) {
+                Ok(json) => println!("{}", json),
+                Err(_) => eprintln!("Error: {:#}", err),
+            }
+        } else {
+            eprintln!("Error: {:#}", err);
+            if let Some(hint) = &info.hint {
+                eprintln!("Hint: {}", hint);
+            }
+        }
+        std::process::exit(exit_code(&info.code));
+    }
+}
+
+/// Exit on a command-line usage error: as a JSON envelope on stdout when the arguments
+/// ask for JSON output, as clap renders it otherwise
+fn usage_error(err: clap::Error, args: &[OsString]) -> ! {
+    use clap::error::ErrorKind;
+
+    let informational = matches!(
+        err.kind(),
+        ErrorKind::DisplayHelp
+            | ErrorKind::DisplayVersion
+            | ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
+    );
+    if informational || !wants_json(args) {
+        err.exit();
+    }
+
+    let events = args
+        .iter()
+        .any(|arg| arg.to_string_lossy().starts_with("--events"));
+    match error_json(usage_error_info(&err), events) {
+        Ok(json) => println!("{}", json),
+        Err(_) => eprint!("{}", err),
+    }
+    std::process::exit(exit_code("usage_error"));
+}
+
+/// Render a failure for stdout: as the final `error` event when it carries
+/// `--events` lines, as an error envelope otherwise
+fn error_json(info: ErrorInfo, events: bool) -> serde_json::Result<String> {
+    if events {
+        serde_json::to_string(&EventLine::new(InstallEvent::from(info)))
+    } else {
+        serde_json::to_string(&ErrorEnvelope {
+            ok: false,
+            error: info,
+        })
+    }
+}
+
+/// Whether raw arguments that failed to parse asked for machine-readable output, as
+/// [`Commands::json_output`] decides for parsed ones
+fn wants_json(args: &[OsString]) -> bool {
+    let args: Vec<_> = args.iter().map(|arg| arg.to_string_lossy()).collect();
+    args.iter().enumerate().any(|(i, arg)| {
+        let output = match arg.strip_prefix("--output") {
+            Some("") => args.get(i + 1).map(|value| value.as_ref()),
+            Some(value) => value.strip_prefix('='),
+            None => None,
+        };
+        arg == "--json"
+            || arg.starts_with("--events")
+            || matches!(output, Some("json" | "json-schema"))
+    })
+}
+
+fn usage_error_info(err: &clap::Error) -> ErrorInfo {
+    let rendered = err.to_string();
+    let message = rendered.lines().next().unwrap_or_default();
+    ErrorInfo {
+        code: "usage_error".to_string(),
+        message: message.trim_start_matches("error: ").to_string(),
+        hint: hint_for("usage_error").map(str::to_string),
+        path: None,
+    }
 }
 
-fn main() -> Result<()> {
-    let cli = Cli::parse();
+fn run(cli: Cli) -> Result<()> {
+    let Cli {
+        command,
+        lock_format,
+        root,
+        home,
+    } = cli;
 
-    match cli.command {
+    match command {
         Commands::Commands { output } => {
             if output.as_deref() == Some("json") {
                 let json = output_commands_json()?;
@@ -62,13 +150,33 @@ fn main() -> Result<()> {
             } else {
                 println!("Available commands:");
                 println!("  commands --output json");
-                println!("  schema --command <name> --output json-schema");
+                println!(
+                    "  schema --command <name> --output json-schema [--kind input|result|events]"
+                );
+                println!("  schema --kind lock --output json-schema");
                 println!("  install-skills [--global] [--yes] [--non-interactive]");
+                println!("  sync [--manifest <path>] [--dry-run]");
+                println!("  lock doctor [--global] [--dry-run]");
+                println!("  scopes");
             }
         }
-        Commands::Schema { command, output } => {
+        Commands::Schema {
+            command,
+            output,
+            kind,
+        } => {
             if output.as_deref() == Some("json-schema") {
-                let schema = get_command_schema(&command)?;
+                let command = match (kind, command) {
+                    (SchemaKind::Lock, _) => String::new(),
+                    (_, Some(command)) => command,
+                    (_, None) => return Err(CliError::MissingSchemaCommand.into()),
+                };
+                let schema = match kind {
+                    SchemaKind::Input => get_command_schema(&command)?,
+                    SchemaKind::Result => get_command_result_schema(&command)?,
+                    SchemaKind::Events => get_command_events_schema(&command)?,
+                    SchemaKind::Lock => get_lock_schema()?,
+                };
                 println!("{}", schema);
             } else {
                 println!("Use --output json-schema to get the schema");
@@ -77,24 +185,142 @@ fn main() -> Result<()> {
         Commands::InstallSkills {
             agent,
             skill,
+            exclude,
             global,
             yes,
             non_interactive,
             json,
+            events,
+            frozen,
         } => {
+            let scope = resolve_scope(global, root, home)?;
+            if frozen {
+                frozen_install_command(&agent, &scope, json, lock_format)?;
+                return Ok(());
+            }
             install_skill_command(
                 &agent,
-                skill.as_deref(),
-                global,
+                SkillFilter::new(&skill, &exclude),
+                &scope,
                 yes || non_interactive,
                 json,
+                events.is_some(),
+                lock_format,
             )?;
         }
+        Commands::Sync {
+            manifest,
+            dry_run,
+            json,
+        } => {
+            let scope = resolve_scope(false, root, home)?;
+            sync_command(&manifest, &scope, dry_run, json, lock_format, None)?;
+        }
+        Commands::Lock {
+            command:
+                LockCommands::Doctor {
+                    global,
+                    dry_run,
+                    json,
+                },
+        } => {
+            let scope = resolve_scope(global, root, home)?;
+            lock_doctor_command(&scope, dry_run, json, lock_format)?;
+        }
+        Commands::Scopes { json } => {
+            scopes_command(root, home, json)?;
+        }
     }
 
     Ok(())
 }
 
+/// Map an error code to the process exit code documented in `EXIT_CODES_HELP`
+fn exit_code(code: &str) -> i32 {
+    match code {
+        "unknown_agent"
+        | "invalid_manifest"
+        | "command_not_found"
+        | "schema_not_found"
+        | "invalid_source"
+        | "confirmation_required"
+        | "usage_error"
+        | "conflicting_scope" => 2,
+        "invalid_skill" | "invalid_skill_name" | "skill_not_found" | "duplicate_skill" => 3,
+        "io_error" | "install_failed" | "serialize_error" => 4,
+        "lock_corrupt" | "lock_invalid" | "lock_mismatch" => 5,
+        "unsafe_path" => 6,
+        "provider_error" => 7,
+        _ => 1,
+    }
+}
+
+/// Convert an error into the machine-readable details printed in JSON mode
+fn error_info(err: &anyhow::Error) -> ErrorInfo {
+    let (code, path) = if let Some(e) = err.downcast_ref::<SkillsError>() {
+        (e.code(), e.path().map(|p| p.display().to_string()))
+    } else if let Some(e) = err.downcast_ref::<CliError>() {
+        match e {
+            CliError::UnknownAgent(_) => ("unknown_agent", None),
+            CliError::ConfirmationRequired(_) => ("confirmation_required", None),
+            CliError::MissingSchemaCommand => ("usage_error", None),
+            CliError::ConflictingScope => ("conflicting_scope", None),
+        }
+    } else if err.downcast_ref::<std::io::Error>().is_some() {
+        ("io_error", None)
+    } else {
+        ("internal_error", None)
+    };
+
+    ErrorInfo {
+        code: code.to_string(),
+        message: format!("{:#}", err),
+        hint: hint_for(code).map(str::to_string),
+        path,
+    }
+}
+
+/// Suggested next step for an error class
+fn hint_for(code: &str) -> Option<&'static str> {
+    match code {
+        "unknown_agent" => Some("Use --agent claude or --agent opencode"),
+        "confirmation_required" => {
+            Some("Pass --yes (or --non-interactive) to install without prompting")
+        }
+        "usage_error" => Some("Run with --help to see the accepted arguments"),
+        "conflicting_scope" => Some("Drop --global to install into the --root directory"),
+        "command_not_found" => Some("Run `commands --output json` to list available commands"),
+        "invalid_skill" => {
+            Some("Check that SKILL.md starts with frontmatter containing `name` and `description`")
+        }
+        "invalid_manifest" => Some("Check the manifest against the format described in USAGE.md"),
+        "invalid_skill_name" => {
+            Some("Skill names may only contain lowercase letters, digits and single hyphens")
+        }
+        "duplicate_skill" => {
+            Some("Rename one of the skills, narrow the selection with --skill/--exclude, or pass --yes to replace a skill installed from another source")
+        }
+        "lock_corrupt" => {
+            Some("Fix or remove the lock file; it is recreated on the next install")
+        }
+        "lock_invalid" => Some(
+            "Run `lock doctor` to repair the lock file; see `schema --kind lock --output json-schema` for its format",
+        ),
+        "lock_mismatch" => {
+            Some("The source no longer matches the lock; run install without --frozen to update it")
+        }
+        "unsafe_path" => Some(
+            "The skill would write outside its directory; inspect the skill and any symlinks in the install directory",
+        ),
+        "install_failed" => {
+            Some("Check permissions of the target directory or configure a fallback install mode")
+        }
+        "io_error" => Some("Check that the path exists and is writable"),
+        "provider_error" => Some("Check network access and the source URL"),
+        _ => None,
+    }
+}
+
 /// Parse agent names from CLI input, handling comma-separated values and deduplication
 fn parse_agents(agents: &[String]) -> Result<Vec<String>> {
     let mut seen = std::collections::HashSet::new();
@@ -114,28 +340,24 @@ fn parse_agents(agents: &[String]) -> Result<Vec<String>> {
 
 /// Resolve agent names to target directories
 /// For OpenCode, returns target dir only in global scope (since project scope uses .agents/skills as universal location)
-fn resolve_target_dirs(
-    agents: &[String],
-    base_dir: &Path,
-    is_global: bool,
-) -> Result<Vec<PathBuf>> {
+fn resolve_target_dirs(agents: &[String], scope: &Scope) -> Result<Vec<PathBuf>> {
     let mut target_dirs = Vec::new();
 
     for agent in agents {
         match agent.as_str() {
             "claude" => {
                 // Claude always uses agent-specific directory
-                target_dirs.push(base_dir.join(".claude/skills"));
+                target_dirs.push(scope.root.join(".claude/skills"));
             }
             "opencode" => {
-                if is_global {
-                    // Global scope: use ~/.config/opencode/skills as target
-                    target_dirs.push(base_dir.join(".config/opencode/skills"));
+                if let Some(paths) = &scope.paths {
+                    // Global scope: use $XDG_CONFIG_HOME/opencode/skills as target
+                    target_dirs.push(paths.opencode_skills_dir());
                 }
                 // Project scope: .agents/skills is universal, no additional target dir needed
             }
             _ => {
-                anyhow::bail!("Unknown agent: '{}'. Known agents: claude, opencode", agent);
+                return Err(CliError::UnknownAgent(agent.clone()).into());
             }
         }
     }
@@ -143,32 +365,192 @@ fn resolve_target_dirs(
     Ok(target_dirs)
 }
 
-/// JSON output structure for install-skills command
-#[derive(Debug, Serialize, Deserialize)]
-struct InstallResult {
-    ok: bool,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    installed_skills: Option<Vec<InstalledSkill>>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    error: Option<String>,
+/// Scope a command works in: `--global` (under `--home`), else `--root`, else the
+/// current project
+fn resolve_scope(global: bool, root: Option<PathBuf>, home: Option<PathBuf>) -> Result<Scope> {
+    match (global, root) {
+        (true, Some(_)) => Err(CliError::ConflictingScope.into()),
+        (true, None) => Ok(Scope::global(&Paths::resolve(home)?)),
+        (false, Some(root)) => Ok(Scope::custom(root)),
+        (false, None) => Ok(Scope::project(std::env::current_dir()?)),
+    }
+}
+
+/// Check the lock file and repair what can be repaired
+fn lock_doctor_command(
+    scope: &Scope,
+    dry_run: bool,
+    json_output: bool,
+    lock_format: LockFormat,
+) -> Result<()> {
+    let lock_path = scope.lock_path();
+
+    let report = LockManager::new(lock_path.clone())
+        .with_format(lock_format)
+        .doctor(dry_run)?;
+
+    if json_output {
+        let output = LockDoctorOutput {
+            schema_version: cli::SCHEMA_VERSION.to_string(),
+            ok: true,
+            dry_run,
+            fixed: report.fixed,
+            remaining: report.remaining,
+        };
+        println!("{}", serde_json::to_string(&output)?);
+        return Ok(());
+    }
+
+    for issue in &report.fixed {
+        println!("Fixed {}", issue);
+    }
+    for issue in &report.remaining {
+        let note = if issue.is_fixable() {
+            ""
+        } else {
+            " (manual fix needed)"
+        };
+        println!("Found {}{}", issue, note);
+    }
+    if report.fixed.is_empty() && report.remaining.is_empty() {
+        println!("No problems found in {}", lock_path.display());
+    }
+
+    Ok(())
 }
 
-#[derive(Debug, Serialize, Deserialize)]
-struct InstalledSkill {
-    name: String,
-    description: String,
-    canonical_path: String,
-    target_paths: Vec<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    symlink_failed: Option<bool>,
+/// Warning shown when a skill is reinstalled from a different source than before
+fn origin_changed_message(name: &str, previous: &types::PreviousSource) -> String {
+    format!(
+        "Skill '{}' changed origin; it was previously installed from {}",
+        name, previous
+    )
 }
 
-fn install_skill_command(
+/// List the skills of the project, global and `--root` scopes together
+fn scopes_command(root: Option<PathBuf>, home: Option<PathBuf>, json_output: bool) -> Result<()> {
+    let mut scopes = vec![
+        Scope::project(std::env::current_dir()?),
+        Scope::global(&Paths::resolve(home)?),
+    ];
+    scopes.extend(root.map(Scope::custom));
+    let view = combined_view(&scopes)?;
+
+    if json_output {
+        let output = ScopesOutput {
+            schema_version: cli::SCHEMA_VERSION.to_string(),
+            ok: true,
+            view,
+        };
+        println!("{}", serde_json::to_string(&output)?);
+        return Ok(());
+    }
+
+    for summary in &view.scopes {
+        println!(
+            "{}: {} skill(s) in {}",
+            summary.scope.as_str(),
+            summary.skills,
+            summary.lock_path
+        );
+    }
+    if !view.skills.is_empty() {
+        println!();
+    }
+    for skill in &view.skills {
+        let shadowed = match skill.shadowed_by {
+            Some(by) => format!(" (shadowed by {})", by.as_str()),
+            None => String::new(),
+        };
+        println!(
+            "  {} [{}] {}{}",
+            skill.name,
+            skill.scope.as_str(),
+            skill.path,
+            shadowed
+        );
+    }
+
+    Ok(())
+}
+
+/// Reinstall every skill recorded in the lock file without modifying it
+fn frozen_install_command(
     agents: &[String],
-    skill_filter: Option<&str>,
-    is_global: bool,
-    auto_confirm: bool,
+    scope: &Scope,
     json_output: bool,
+    lock_format: LockFormat,
+) -> Result<()> {
+    macro_rules! log_msg {
+        ($($arg:tt)*) => {
+            if json_output {
+                eprintln!($($arg)*);
+            } else {
+                println!($($arg)*);
+            }
+        };
+    }
+
+    let lock_path = scope.lock_path();
+
+    let normalized_agents = parse_agents(agents)?;
+    let mut config = scope.install_config();
+    if !normalized_agents.is_empty() {
+        config.target_dirs = resolve_target_dirs(&normalized_agents, scope)?;
+    }
+
+    let lock = LockManager::new(lock_path.clone())
+        .with_format(lock_format)
+        .load()?;
+    log_msg!(
+        "Installing {} skill(s) from {}",
+        lock.skills.len(),
+        lock_path.display()
+    );
+    let results = install_frozen(&lock, &config, &DiscoveryConfig::default(), None)?;
+
+    let mut installed_skills = Vec::new();
+    for (skill, result) in results {
+        log_msg!("  {} -> {}", skill.name, result.path.display());
+        let targets: Vec<InstalledTarget> = result
+            .targets
+            .iter()
+            .map(|target| InstalledTarget {
+                path: target.path.display().to_string(),
+                requested_mode: target.requested_mode,
+                effective_mode: target.effective_mode,
+                fallback_error: target.fallback_error.clone(),
+            })
+            .collect();
+        installed_skills.push(InstalledSkill {
+            name: skill.name,
+            description: skill.description,
+            canonical_path: result.path.display().to_string(),
+            target_paths: targets.iter().map(|t| t.path.clone()).collect(),
+            targets,
+            symlink_failed: result.symlink_failed.then_some(true),
+        });
+    }
+
+    log_msg!("\nInstallation complete!");
+
+    if json_output {
+        let result = InstallSkillsOutput::new(installed_skills);
+        println!("{}", serde_json::to_string(&result)?);
+    }
+
+    Ok(())
+}
+
+/// Make the scope's installed skills match the manifest; sources that need one are
+/// fetched through `provider`
+fn sync_command(
+    manifest_path: &Path,
+    scope: &Scope,
+    dry_run: bool,
+    json_output: bool,
+    lock_format: LockFormat,
+    provider: Option<&dyn SkillProvider>,
 ) -> Result<()> {
     // Macro to log messages: to stderr in JSON mode, stdout otherwise
     macro_rules! log_msg {
@@ -181,75 +563,329 @@ fn install_skill_command(
         };
     }
 
+    let lock_manager = LockManager::new(scope.lock_path()).with_format(lock_format);
+
+    let manifest = Manifest::load(&scope.root.join(manifest_path))?;
+    let lock = lock_manager.load()?;
+    let mut plan = plan_sync(&manifest, &lock, &DiscoveryConfig::default(), provider)?;
+    for invalid in &plan.invalid {
+        log_msg!("Warning: Skipping {}: {}", invalid.path, invalid.reason);
+    }
+
+    // Skills may be linked into any agent directory, including ones the manifest dropped
+    let all_agents: Vec<String> = KNOWN_AGENTS.iter().map(|a| a.to_string()).collect();
+    let all_target_dirs = resolve_target_dirs(&all_agents, scope)?;
+    let target_dirs = |planned: &PlannedSkill| -> Result<(Vec<PathBuf>, Vec<PathBuf>)> {
+        let declared = resolve_target_dirs(&parse_agents(&planned.agents)?, scope)?;
+        let undeclared = all_target_dirs
+            .iter()
+            .filter(|dir| !declared.contains(dir))
+            .cloned()
+            .collect();
+        Ok((declared, undeclared))
+    };
+
+    // An unchanged skill still needs relinking if the manifest's agents changed
+    let mut unchanged = Vec::new();
+    for planned in std::mem::take(&mut plan.unchanged) {
+        let (declared, undeclared) = target_dirs(&planned)?;
+        let exists =
+            |dir: &PathBuf| std::fs::symlink_metadata(dir.join(&planned.skill.name)).is_ok();
+        let linked = declared.iter().all(exists) && !undeclared.iter().any(exists);
+        if linked {
+            unchanged.push(planned);
+        } else {
+            plan.update.push(planned);
+        }
+    }
+    plan.unchanged = unchanged;
+
+    let names = |planned: &[PlannedSkill]| -> Vec<String> {
+        planned.iter().map(|p| p.skill.name.clone()).collect()
+    };
+    let output = SyncOutput {
+        schema_version: cli::SCHEMA_VERSION.to_string(),
+        ok: true,
+        dry_run,
+        installed: names(&plan.install),
+        updated: names(&plan.update),
+        removed: plan.remove.clone(),
+        unchanged: names(&plan.unchanged),
+    };
+
+    let verb = |done: &'static str, planned: &'static str| if dry_run { planned } else { done };
+    let changes = plan
+        .install
+        .iter()
+        .map(|p| (p, verb("Installed", "Would install")))
+        .chain(
+            plan.update
+                .iter()
+                .map(|p| (p, verb("Updated", "Would update"))),
+        );
+    for (planned, action) in changes {
+        if !dry_run {
+            let (declared, undeclared) = target_dirs(planned)?;
+            let mut install_config = scope.install_config();
+            install_config.target_dirs = declared;
+            let provider = provider.filter(|_| planned.source.source_type.requires_provider());
+            let result = install_skill_with_provider(&planned.skill, &install_config, provider)?;
+            unlink_skill(&planned.skill.name, &undeclared)?;
+
+            let folder_hash = match provider {
+                Some(provider) => provider.get_folder_hash(&planned.skill).map_err(|error| {
+                    SkillsError::Provider {
+                        context: format!("Failed to hash skill {:?}", planned.skill.name),
+                        error,
+                    }
+                })?,
+                None => compute_skill_hash(&result.path)?,
+            };
+            let previous = lock_manager.update_skill_entry(
+                &planned.skill,
+                &planned.source,
+                &result.path,
+                folder_hash,
+                provider,
+            )?;
+            if let Some(previous) = previous {
+                log_msg!(
+                    "Warning: {}",
+                    origin_changed_message(&planned.skill.name, &previous)
+                );
+            }
+        }
+        log_msg!("{} {}", action, planned.skill.name);
+    }
+
+    for name in &plan.remove {
+        if !dry_run {
+            let mut install_config = scope.install_config();
+            install_config.target_dirs = all_target_dirs.clone();
+            uninstall_skill(name, &install_config)?;
+            lock_manager.remove_entry(name)?;
+        }
+        log_msg!("{} {}", verb("Removed", "Would remove"), name);
+    }
+
+    if plan.is_empty() {
+        log_msg!("Skills are in sync with {}", manifest_path.display());
+    }
+
+    if json_output {
+        println!("{}", serde_json::to_string(&output)?);
+    }
+
+    Ok(())
+}
+
+/// Ask the user which of the discovered skills to install; an empty answer selects none
+fn prompt_selection(skills: Vec<Skill>) -> Result<Vec<Skill>> {
+    println!("\nSelect skills to install (e.g. 1,3-4 or all; empty to cancel):");
+    for (i, skill) in skills.iter().enumerate() {
+        println!("  {}) {}", i + 1, skill.name);
+    }
+
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let mut input = String::new();
+        if std::io::stdin().read_line(&mut input)? == 0 {
+            return Ok(Vec::new());
+        }
+        match parse_selection(&input, skills.len()) {
+            Ok(indices) => {
+                return Ok(skills
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(i, _)| indices.contains(i))
+                    .map(|(_, skill)| skill)
+                    .collect())
+            }
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+/// Parse a selection such as `1,3-4` or `all` into zero-based indices.
+///
+/// Numbers are one-based and may be separated by commas or whitespace. Errors are
+/// messages for the prompt, which asks again.
+fn parse_selection(input: &str, count: usize) -> Result<BTreeSet<usize>, String> {
+    let invalid = |reason: String| format!("Invalid selection '{}': {}", input.trim(), reason);
+    let parse_number = |token: &str| -> Result<usize, String> {
+        let n: usize = token
+            .trim()
+            .parse()
+            .map_err(|_| invalid(format!("'{}' is not a number", token.trim())))?;
+        if n == 0 || n > count {
+            return Err(invalid(format!("{} is out of range 1-{}", n, count)));
+        }
+        Ok(n - 1)
+    };
+
+    let mut selected = BTreeSet::new();
+    for token in input
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|t| !t.is_empty())
+    {
+        if token.eq_ignore_ascii_case("all") || token == "*" {
+            selected.extend(0..count);
+        } else if let Some((start, end)) = token.split_once('-') {
+            let (start, end) = (parse_number(start)?, parse_number(end)?);
+            if start > end {
+                return Err(invalid(format!("range '{}' is reversed", token)));
+            }
+            selected.extend(start..=end);
+        } else {
+            selected.insert(parse_number(token)?);
+        }
+    }
+    Ok(selected)
+}
+
+fn install_skill_command(
+    agents: &[String],
+    skill_filter: SkillFilter,
+    scope: &Scope,
+    auto_confirm: bool,
+    json_output: bool,
+    events: bool,
+    lock_format: LockFormat,
+) -> Result<()> {
+    // Macro to log messages: to stderr when stdout carries JSON, stdout otherwise
+    macro_rules! log_msg {
+        ($($arg:tt)*) => {
+            if json_output || events {
+                eprintln!($($arg)*);
+            } else {
+                println!($($arg)*);
+            }
+        };
+    }
+
+    // Macro to write one NDJSON progress event to stdout
+    macro_rules! emit {
+        ($event:expr) => {
+            if events {
+                println!("{}", serde_json::to_string(&EventLine::new($event))?);
+            }
+        };
+    }
+
     let source = Source {
         source_type: SourceType::Self_,
         url: None,
         subpath: None,
-        skill_filter: skill_filter.map(|s| s.to_string()),
+        skill_filter: (!skill_filter.is_empty()).then(|| skill_filter.clone()),
         ref_: None,
     };
 
-    // Setup paths
-    let base_dir = if is_global {
-        let base_dirs = BaseDirs::new().context("Failed to determine home directory")?;
-        base_dirs.home_dir().to_path_buf()
-    } else {
-        std::env::current_dir()?
-    };
-    let canonical_dir = base_dir.join(".agents/skills");
-    let lock_path = base_dir.join(".agents/.skill-lock.json");
+    let lock_path = scope.lock_path();
 
     // Parse and normalize agent names
     let normalized_agents = parse_agents(agents)?;
 
-    if is_global {
-        log_msg!("Discovering embedded skills (scope: global)");
-    } else {
-        log_msg!("Discovering embedded skills (scope: project)");
+    // Prompts can't be answered when stdout carries JSON or stdin isn't a terminal
+    if !auto_confirm {
+        if json_output {
+            return Err(CliError::ConfirmationRequired("--json").into());
+        }
+        if events {
+            return Err(CliError::ConfirmationRequired("--events").into());
+        }
+        if !std::io::stdin().is_terminal() {
+            return Err(CliError::ConfirmationRequired("Non-interactive stdin").into());
+        }
     }
 
+    log_msg!(
+        "Discovering embedded skills (scope: {})",
+        scope.kind.as_str()
+    );
+
     // Discover skills
     let config = DiscoveryConfig::default();
-    let mut skills = discover_skills(&source, &config)?;
+    let report = discover_skills_with_report(&source, &config, None)?;
+    let mut skills = report.skills;
+    for duplicate in &report.duplicates {
+        let message = format!(
+            "Duplicate skill '{}': using {}, ignoring {}",
+            duplicate.name,
+            duplicate.kept.as_deref().unwrap_or("<unknown path>"),
+            duplicate.duplicate.as_deref().unwrap_or("<unknown path>")
+        );
+        log_msg!("Warning: {}", message);
+        emit!(InstallEvent::Warning {
+            skill: Some(duplicate.name.clone()),
+            message,
+        });
+    }
+    for invalid in &report.invalid {
+        let message = format!("Skipping {}: {}", invalid.path, invalid.reason);
+        log_msg!("Warning: {}", message);
+        emit!(InstallEvent::Warning {
+            skill: None,
+            message,
+        });
+    }
 
     if skills.is_empty() {
-        log_msg!("No skills found.");
+        let message = if skill_filter.is_empty() {
+            "No skills found".to_string()
+        } else {
+            format!("No skill matching '{}' found", skill_filter)
+        };
+        log_msg!("{}.", message);
+        emit!(InstallEvent::Warning {
+            skill: None,
+            message,
+        });
         if json_output {
-            let result = InstallResult {
-                ok: true,
-                installed_skills: Some(vec![]),
-                error: None,
-            };
+            let result = InstallSkillsOutput::new(vec![]);
             println!("{}", serde_json::to_string(&result)?);
         }
         return Ok(());
     }
 
-    // Filter by skill name if specified
-    if let Some(filter) = skill_filter {
-        skills.retain(|s| s.name == filter);
+    log_msg!("Found {} skill(s):", skills.len());
+    for skill in &skills {
+        log_msg!("  - {} ({})", skill.name, skill.description);
+    }
+    emit!(InstallEvent::Discovered {
+        skills: skills.iter().map(|s| s.name.clone()).collect(),
+    });
+
+    if !auto_confirm {
+        skills = prompt_selection(skills)?;
         if skills.is_empty() {
-            log_msg!("No skill matching '{}' found.", filter);
-            if json_output {
-                let result = InstallResult {
-                    ok: true,
-                    installed_skills: Some(vec![]),
-                    error: None,
-                };
-                println!("{}", serde_json::to_string(&result)?);
-            }
+            log_msg!("No skills selected.");
             return Ok(());
         }
     }
 
-    log_msg!("Found {} skill(s):", skills.len());
-    for skill in &skills {
-        log_msg!("  - {} ({})", skill.name, skill.description);
+    // A skill of the same name installed from another source would be replaced; only
+    // do that when asked to
+    let lock = LockManager::new(lock_path.clone())
+        .with_format(lock_format)
+        .load()?;
+    if let Some(conflict) = find_lock_conflicts(&lock, &skills, &source)
+        .into_iter()
+        .next()
+    {
+        if !auto_confirm || config.on_duplicate == DuplicatePolicy::Error {
+            return Err(SkillsError::DuplicateSkill {
+                name: conflict.name,
+                first: conflict.kept.map(PathBuf::from),
+                second: conflict.duplicate.map(PathBuf::from),
+            }
+            .into());
+        }
     }
 
     // Resolve target directories if agents specified
     let target_dirs = if !normalized_agents.is_empty() {
-        resolve_target_dirs(&normalized_agents, &base_dir, is_global)?
+        resolve_target_dirs(&normalized_agents, scope)?
     } else {
         Vec::new()
     };
@@ -259,47 +895,91 @@ fn install_skill_command(
 
     // Install each skill
     for skill in &skills {
-        if !auto_confirm && !json_output {
-            println!("\nInstall skill '{}'? (y/n)", skill.name);
-            let mut input = String::new();
-            std::io::stdin().read_line(&mut input)?;
-            if !input.trim().eq_ignore_ascii_case("y") {
-                log_msg!("Skipped.");
-                continue;
-            }
-        }
-
         log_msg!("Installing skill '{}'...", skill.name);
+        emit!(InstallEvent::Fetching {
+            skill: skill.name.clone(),
+            source: source.url.clone().unwrap_or_else(|| "self".to_string()),
+        });
 
         // Install to canonical directory and link/copy to target directories
-        let mut install_config = InstallConfig::new(canonical_dir.clone());
+        let mut install_config = scope.install_config();
         install_config.target_dirs = target_dirs.clone();
         let result = install_skill(skill, &install_config)?;
 
         log_msg!("  Installed to: {}", result.path.display());
+        emit!(InstallEvent::Installed {
+            skill: skill.name.clone(),
+            path: result.path.display().to_string(),
+        });
 
         // Report target directories
         let mut target_paths = Vec::new();
-        for target_dir in &target_dirs {
-            let target_path = target_dir.join(&skill.name);
-            log_msg!("  Linked to: {}", target_path.display());
-            target_paths.push(target_path.display().to_string());
+        let mut targets = Vec::new();
+        for target in &result.targets {
+            log_msg!(
+                "  Linked to: {} ({})",
+                target.path.display(),
+                target.effective_mode.as_str()
+            );
+            if let Some(error) = &target.fallback_error {
+                log_msg!(
+                    "    {} failed, used {}: {}",
+                    target.requested_mode.as_str(),
+                    target.effective_mode.as_str(),
+                    error
+                );
+            }
+            let installed_target = InstalledTarget {
+                path: target.path.display().to_string(),
+                requested_mode: target.requested_mode,
+                effective_mode: target.effective_mode,
+                fallback_error: target.fallback_error.clone(),
+            };
+            emit!(InstallEvent::Linked {
+                skill: skill.name.clone(),
+                target: installed_target.clone(),
+            });
+            target_paths.push(target.path.display().to_string());
+            targets.push(installed_target);
         }
 
         if result.symlink_failed {
-            log_msg!("  Note: Some symlinks failed, used copy fallback.");
+            log_msg!("  Note: Some symlinks failed, used fallback mode.");
+            emit!(InstallEvent::Warning {
+                skill: Some(skill.name.clone()),
+                message: "Some symlinks failed, used fallback mode".to_string(),
+            });
         }
 
-        let lock_manager = LockManager::new(lock_path.clone());
-        lock_manager.update_entry(&skill.name, &source, &result.path)?;
+        let lock_manager = LockManager::new(lock_path.clone()).with_format(lock_format);
+        let previous = lock_manager.update_skill_entry(
+            skill,
+            &source,
+            &result.path,
+            compute_skill_hash(&result.path)?,
+            None,
+        )?;
+        if let Some(previous) = previous {
+            let message = origin_changed_message(&skill.name, &previous);
+            log_msg!("  Warning: {}", message);
+            emit!(InstallEvent::Warning {
+                skill: Some(skill.name.clone()),
+                message,
+            });
+        }
 
         log_msg!("  Lock file updated: {}", lock_path.display());
+        emit!(InstallEvent::LockUpdated {
+            skill: skill.name.clone(),
+            path: lock_path.display().to_string(),
+        });
 
         installed_skills.push(InstalledSkill {
             name: skill.name.clone(),
             description: skill.description.clone(),
             canonical_path: result.path.display().to_string(),
             target_paths,
+            targets,
             symlink_failed: if result.symlink_failed {
                 Some(true)
             } else {
@@ -312,11 +992,7 @@ fn install_skill_command(
 
     // Output JSON result if requested
     if json_output {
-        let result = InstallResult {
-            ok: true,
-            installed_skills: Some(installed_skills),
-            error: None,
-        };
+        let result = InstallSkillsOutput::new(installed_skills);
         println!("{}", serde_json::to_string(&result)?);
     }
 
@@ -327,6 +1003,7 @@ fn install_skill_command(
 mod tests {
     use super::*;
     use agent_skills_rs::types::SkillMetadata;
+    use clap::Parser;
 
     #[test]
     fn test_parse_agents_single() {
@@ -382,7 +1059,7 @@ mod tests {
     fn test_resolve_target_dirs_claude() {
         let agents = vec!["claude".to_string()];
         let base_dir = PathBuf::from("/home/user");
-        let result = resolve_target_dirs(&agents, &base_dir, false).unwrap();
+        let result = resolve_target_dirs(&agents, &Scope::project(&base_dir)).unwrap();
         assert_eq!(result, vec![PathBuf::from("/home/user/.claude/skills")]);
     }
 
@@ -390,7 +1067,7 @@ mod tests {
     fn test_resolve_target_dirs_opencode_project_scope() {
         let agents = vec!["opencode".to_string()];
         let base_dir = PathBuf::from("/home/user/project");
-        let result = resolve_target_dirs(&agents, &base_dir, false).unwrap();
+        let result = resolve_target_dirs(&agents, &Scope::project(&base_dir)).unwrap();
         // Project scope: no additional target dir (uses canonical .agents/skills)
         assert_eq!(result, Vec::<PathBuf>::new());
     }
@@ -399,7 +1076,7 @@ mod tests {
     fn test_resolve_target_dirs_opencode_global_scope() {
         let agents = vec!["opencode".to_string()];
         let base_dir = PathBuf::from("/home/user");
-        let result = resolve_target_dirs(&agents, &base_dir, true).unwrap();
+        let result = resolve_target_dirs(&agents, &Scope::global(&Paths::new(&base_dir))).unwrap();
         // Global scope: adds ~/.config/opencode/skills as target
         assert_eq!(
             result,
@@ -407,11 +1084,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_resolve_target_dirs_opencode_xdg_config_home() {
+        let agents = vec!["opencode".to_string()];
+        let paths = Paths::new("/home/user").with_config_dir("/xdg");
+        let result = resolve_target_dirs(&agents, &Scope::global(&paths)).unwrap();
+        assert_eq!(result, vec![PathBuf::from("/xdg/opencode/skills")]);
+    }
+
     #[test]
     fn test_resolve_target_dirs_multiple_project_scope() {
         let agents = vec!["claude".to_string(), "opencode".to_string()];
         let base_dir = PathBuf::from("/home/user/project");
-        let result = resolve_target_dirs(&agents, &base_dir, false).unwrap();
+        let result = resolve_target_dirs(&agents, &Scope::project(&base_dir)).unwrap();
         // Project scope: only claude gets target dir
         assert_eq!(
             result,
@@ -423,7 +1108,7 @@ mod tests {
     fn test_resolve_target_dirs_multiple_global_scope() {
         let agents = vec!["claude".to_string(), "opencode".to_string()];
         let base_dir = PathBuf::from("/home/user");
-        let result = resolve_target_dirs(&agents, &base_dir, true).unwrap();
+        let result = resolve_target_dirs(&agents, &Scope::global(&Paths::new(&base_dir))).unwrap();
         // Global scope: both get target dirs
         assert_eq!(
             result,
@@ -438,7 +1123,7 @@ mod tests {
     fn test_resolve_target_dirs_unknown_agent() {
         let agents = vec!["unknown".to_string()];
         let base_dir = PathBuf::from("/home/user");
-        let result = resolve_target_dirs(&agents, &base_dir, false);
+        let result = resolve_target_dirs(&agents, &Scope::project(&base_dir));
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -450,10 +1135,345 @@ mod tests {
     fn test_resolve_target_dirs_empty() {
         let agents = vec![];
         let base_dir = PathBuf::from("/home/user");
-        let result = resolve_target_dirs(&agents, &base_dir, false).unwrap();
+        let result = resolve_target_dirs(&agents, &Scope::project(&base_dir)).unwrap();
         assert_eq!(result, Vec::<PathBuf>::new());
     }
 
+    #[test]
+    fn test_error_info_uses_library_error_code() {
+        let err = anyhow::Error::new(SkillsError::CommandNotFound {
+            name: "nope".to_string(),
+        });
+        let info = error_info(&err);
+        assert_eq!(info.code, "command_not_found");
+        assert_eq!(info.message, "Command not found: nope");
+        assert!(info.hint.is_some());
+        assert_eq!(exit_code(&info.code), 2);
+
+        let info = error_info(&anyhow::anyhow!("something else"));
+        assert_eq!(info.code, "internal_error");
+        assert_eq!(exit_code(&info.code), 1);
+    }
+
+    #[test]
+    fn test_error_info_includes_offending_path() {
+        let err = anyhow::Error::new(SkillsError::UnsafePath {
+            path: PathBuf::from("../escape"),
+            reason: "Auxiliary file path must not traverse outside skill directory".to_string(),
+        });
+        let info = error_info(&err);
+        assert_eq!(info.code, "unsafe_path");
+        assert_eq!(info.path.as_deref(), Some("../escape"));
+        assert_eq!(exit_code(&info.code), 6);
+    }
+
+    #[test]
+    fn test_unknown_agent_error_envelope() {
+        let err = resolve_target_dirs(&["unknown".to_string()], &Scope::project("/p")).unwrap_err();
+        let envelope = ErrorEnvelope {
+            ok: false,
+            error: error_info(&err),
+        };
+
+        let json = serde_json::to_value(&envelope).unwrap();
+        assert_eq!(json["ok"], false);
+        assert_eq!(json["error"]["code"], "unknown_agent");
+        assert_eq!(
+            json["error"]["message"],
+            "Unknown agent: 'unknown'. Known agents: claude, opencode"
+        );
+        assert!(json["error"]["hint"].is_string());
+        assert!(json["error"].get("path").is_none());
+        assert_eq!(exit_code("unknown_agent"), 2);
+    }
+
+    #[test]
+    fn test_error_in_events_stream_is_an_event() {
+        let info = error_info(&anyhow::Error::new(CliError::UnknownAgent("x".to_string())));
+
+        let line: serde_json::Value =
+            serde_json::from_str(&error_json(info.clone(), true).unwrap()).unwrap();
+        assert_eq!(line["event"], "error");
+        assert_eq!(line["code"], "unknown_agent");
+        assert_eq!(line["schemaVersion"], cli::SCHEMA_VERSION);
+        assert!(line.get("ok").is_none());
+
+        let envelope: serde_json::Value =
+            serde_json::from_str(&error_json(info, false).unwrap()).unwrap();
+        assert_eq!(envelope["ok"], false);
+        assert_eq!(envelope["error"]["code"], "unknown_agent");
+
+        let cli = Cli::try_parse_from(["agent-skills-rs", "install-skills", "--events", "ndjson"])
+            .unwrap();
+        assert!(cli.command.events_output());
+        let cli = Cli::try_parse_from(["agent-skills-rs", "install-skills", "--json"]).unwrap();
+        assert!(!cli.command.events_output());
+    }
+
+    #[test]
+    fn test_usage_error_envelope() {
+        let args = |args: &[&str]| -> Vec<OsString> { args.iter().map(OsString::from).collect() };
+        assert!(wants_json(&args(&[
+            "agent-skills-rs",
+            "sync",
+            "--json",
+            "--bogus"
+        ])));
+        assert!(wants_json(&args(&[
+            "agent-skills-rs",
+            "commands",
+            "--output",
+            "json"
+        ])));
+        assert!(wants_json(&args(&[
+            "agent-skills-rs",
+            "commands",
+            "--output=json"
+        ])));
+        assert!(wants_json(&args(&[
+            "agent-skills-rs",
+            "install-skills",
+            "--events=ndjson"
+        ])));
+        assert!(!wants_json(&args(&["agent-skills-rs", "sync", "--bogus"])));
+
+        let err =
+            Cli::try_parse_from(["agent-skills-rs", "sync", "--json", "--bogus"]).unwrap_err();
+        let info = usage_error_info(&err);
+        assert_eq!(info.code, "usage_error");
+        assert_eq!(info.message, "unexpected argument '--bogus' found");
+        assert!(info.hint.is_some());
+        assert_eq!(exit_code(&info.code), 2);
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        let indices = |input: &str| {
+            parse_selection(input, 5)
+                .unwrap()
+                .into_iter()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(indices("1"), vec![0]);
+        assert_eq!(indices("1,3-4"), vec![0, 2, 3]);
+        assert_eq!(indices(" 5 2 2\n"), vec![1, 4]);
+        assert_eq!(indices("all"), vec![0, 1, 2, 3, 4]);
+        assert_eq!(indices("2-3,ALL"), vec![0, 1, 2, 3, 4]);
+        assert!(indices("").is_empty());
+    }
+
+    #[test]
+    fn test_parse_selection_rejects_invalid_input() {
+        for input in ["0", "6", "x", "4-2", "1-", "-3"] {
+            assert!(parse_selection(input, 5).is_err(), "{}", input);
+        }
+        assert_eq!(
+            parse_selection("9", 2).unwrap_err(),
+            "Invalid selection '9': 9 is out of range 1-2"
+        );
+    }
+
+    #[test]
+    fn test_json_without_yes_is_an_error() {
+        let err = install_skill_command(
+            &[],
+            SkillFilter::default(),
+            &Scope::project("."),
+            false,
+            true,
+            false,
+            LockFormat::Auto,
+        )
+        .unwrap_err();
+        let info = error_info(&err);
+        assert_eq!(info.code, "confirmation_required");
+        assert_eq!(
+            info.message,
+            "--json requires --yes; interactive confirmation is not available"
+        );
+        assert_eq!(exit_code(&info.code), 2);
+
+        let err = install_skill_command(
+            &[],
+            SkillFilter::default(),
+            &Scope::project("."),
+            false,
+            false,
+            true,
+            LockFormat::Auto,
+        )
+        .unwrap_err();
+        assert_eq!(error_info(&err).code, "confirmation_required");
+    }
+
+    #[test]
+    fn test_resolve_scope() {
+        let scope = resolve_scope(false, Some(PathBuf::from("/opt/skills")), None).unwrap();
+        assert_eq!(scope, Scope::custom("/opt/skills"));
+        assert_eq!(
+            resolve_scope(false, None, None).unwrap(),
+            Scope::project(std::env::current_dir().unwrap())
+        );
+
+        let err = resolve_scope(true, Some(PathBuf::from("/opt/skills")), None).unwrap_err();
+        let info = error_info(&err);
+        assert_eq!(info.code, "conflicting_scope");
+        assert_eq!(exit_code(&info.code), 2);
+    }
+
+    #[test]
+    fn test_json_output_detection() {
+        assert!(Commands::Commands {
+            output: Some("json".to_string())
+        }
+        .json_output());
+        assert!(!Commands::Commands { output: None }.json_output());
+        assert!(Commands::Schema {
+            command: Some("install-skills".to_string()),
+            output: Some("json-schema".to_string()),
+            kind: SchemaKind::Result,
+        }
+        .json_output());
+
+        let cli = Cli::try_parse_from(["agent-skills-rs", "install-skills", "--events", "ndjson"])
+            .unwrap();
+        assert!(cli.command.json_output());
+        assert!(
+            Cli::try_parse_from(["agent-skills-rs", "install-skills", "--events", "text"]).is_err()
+        );
+        // Both would write to stdout
+        assert!(Cli::try_parse_from([
+            "agent-skills-rs",
+            "install-skills",
+            "--events",
+            "ndjson",
+            "--json"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_frozen_flag() {
+        let cli = Cli::try_parse_from(["agent-skills-rs", "install-skills", "--locked"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::InstallSkills { frozen: true, .. }
+        ));
+        assert!(Cli::try_parse_from([
+            "agent-skills-rs",
+            "install-skills",
+            "--frozen",
+            "--skill",
+            "pdf"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_installed_skill_json_includes_targets() {
+        let installed = InstalledSkill {
+            name: "test-skill".to_string(),
+            description: "Test skill".to_string(),
+            canonical_path: "/p/.agents/skills/test-skill".to_string(),
+            target_paths: vec!["/p/.claude/skills/test-skill".to_string()],
+            targets: vec![InstalledTarget {
+                path: "/p/.claude/skills/test-skill".to_string(),
+                requested_mode: InstallMode::Symlink,
+                effective_mode: InstallMode::Copy,
+                fallback_error: Some("symlink: Failed to create symlink".to_string()),
+            }],
+            symlink_failed: Some(true),
+        };
+
+        let json = serde_json::to_value(&installed).unwrap();
+        let target = &json["targets"][0];
+        assert_eq!(target["path"], "/p/.claude/skills/test-skill");
+        assert_eq!(target["requested_mode"], "symlink");
+        assert_eq!(target["effective_mode"], "copy");
+        assert_eq!(
+            target["fallback_error"],
+            "symlink: Failed to create symlink"
+        );
+    }
+
+    #[test]
+    fn test_sync_remote_source_through_provider() {
+        use tempfile::TempDir;
+
+        let temp_dir = TempDir::new().unwrap();
+        let paths = Paths::new(temp_dir.path()).with_config_dir(temp_dir.path().join(".config"));
+        let scope = Scope::global(&paths);
+        let manifest_path = temp_dir.path().join("skills.toml");
+        let write_manifest = |agents: &str| {
+            std::fs::write(
+                &manifest_path,
+                format!(
+                    "agents = [{}]\n\n[[sources]]\ntype = \"github\"\nurl = \"https://github.com/example/skills\"\n",
+                    agents
+                ),
+            )
+            .unwrap();
+        };
+
+        let skill = Skill {
+            name: "remote-skill".to_string(),
+            description: "Remote skill".to_string(),
+            path: None,
+            raw_content: "---\nname: remote-skill\ndescription: Remote skill\n---\n\n# Remote"
+                .to_string(),
+            metadata: SkillMetadata::default(),
+            auxiliary_files: Default::default(),
+        };
+        let provider = MockProvider::new(vec![skill])
+            .with_hash("remote-hash".to_string())
+            .with_commit("abc123".to_string());
+
+        write_manifest("\"claude\", \"opencode\"");
+        sync_command(
+            &manifest_path,
+            &scope,
+            false,
+            true,
+            LockFormat::Auto,
+            Some(&provider),
+        )
+        .unwrap();
+
+        let entry = LockManager::new(scope.lock_path())
+            .get_entry("remote-skill")
+            .unwrap()
+            .unwrap();
+        assert_eq!(entry.source_type, "github");
+        assert_eq!(entry.skill_folder_hash, "remote-hash");
+        assert_eq!(entry.commit.as_deref(), Some("abc123"));
+        let links =
+            resolve_target_dirs(&["claude".to_string(), "opencode".to_string()], &scope).unwrap();
+        assert_eq!(links.len(), 2);
+        for dir in &links {
+            assert!(dir.join("remote-skill/SKILL.md").exists());
+        }
+
+        // Dropping an agent from the manifest removes the skill's link for it
+        write_manifest("\"claude\"");
+        sync_command(
+            &manifest_path,
+            &scope,
+            false,
+            true,
+            LockFormat::Auto,
+            Some(&provider),
+        )
+        .unwrap();
+        assert!(links[0].join("remote-skill/SKILL.md").exists());
+        assert!(std::fs::symlink_metadata(links[1].join("remote-skill")).is_err());
+        assert!(scope.canonical_dir().join("remote-skill/SKILL.md").exists());
+
+        // Without a provider the remote source can't be synced
+        let err =
+            sync_command(&manifest_path, &scope, false, true, LockFormat::Auto, None).unwrap_err();
+        assert_eq!(error_info(&err).code, "invalid_source");
+    }
+
     #[test]
     #[cfg(unix)]
     fn test_agent_specific_installation_with_symlinks() {
@@ -516,7 +1536,7 @@ mod tests {
 
         // Resolve target dirs for opencode in project scope
         let agents = vec!["opencode".to_string()];
-        let target_dirs = resolve_target_dirs(&agents, base_dir, false).unwrap();
+        let target_dirs = resolve_target_dirs(&agents, &Scope::project(base_dir)).unwrap();
 
         // Should be empty (no additional target dir needed)
         assert_eq!(target_dirs.len(), 0);
@@ -557,7 +1577,8 @@ mod tests {
 
         // Resolve target dirs for opencode in global scope
         let agents = vec!["opencode".to_string()];
-        let target_dirs = resolve_target_dirs(&agents, base_dir, true).unwrap();
+        let target_dirs =
+            resolve_target_dirs(&agents, &Scope::global(&Paths::new(base_dir))).unwrap();
 
         // Should have one target dir
         assert_eq!(target_dirs.len(), 1);
@@ -1,6 +1,178 @@
-use anyhow::{Context, Result};
+use crate::error::{Result, SkillsError};
+use crate::installer::InstallMode;
+use crate::lock::{LockFormat, LockIssue};
+use crate::manifest::MANIFEST_PATH;
+use crate::paths::HOME_ENV;
+use crate::scope::{CombinedView, ROOT_ENV};
+use crate::types::SkillLock;
+use clap::{ArgAction, CommandFactory, Parser, Subcommand, ValueEnum};
+use schemars::gen::SchemaSettings;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use std::any::TypeId;
+use std::path::PathBuf;
+
+/// Command-line interface of the `agent-skills-rs` binary.
+///
+/// The introspection data returned by [`get_commands`] and [`get_command_schema`]
+/// is derived from this definition at runtime, so it can't drift from the parser.
+#[derive(Debug, Parser)]
+#[command(name = "agent-skills-rs")]
+#[command(about = "A CLI tool with skill installation support", long_about = None)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Commands,
+    /// Lock file format to write (auto keeps the format of the existing file)
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        env = "AGENT_SKILLS_LOCK_FORMAT",
+        default_value_t = LockFormat::Auto
+    )]
+    pub lock_format: LockFormat,
+    /// Install into this directory instead of the current project
+    #[arg(long, global = true, value_name = "DIR", env = ROOT_ENV)]
+    pub root: Option<PathBuf>,
+    /// Home directory for global installs (default: the user's home directory)
+    #[arg(long, global = true, value_name = "DIR", env = HOME_ENV)]
+    pub home: Option<PathBuf>,
+}
+
+/// Subcommands of the `agent-skills-rs` binary
+#[derive(Debug, Subcommand)]
+#[allow(clippy::enum_variant_names)]
+pub enum Commands {
+    /// List all available commands
+    Commands {
+        /// Output format (json)
+        #[arg(long, value_name = "FORMAT", value_parser = ["json"])]
+        output: Option<String>,
+    },
+    /// Get JSON schema for a command
+    Schema {
+        /// Command name to get schema for (not used with --kind lock)
+        #[arg(long, value_name = "COMMAND")]
+        command: Option<String>,
+        /// Output format (json-schema)
+        #[arg(long, value_name = "FORMAT", value_parser = ["json-schema"])]
+        output: Option<String>,
+        /// Which schema to print: the command's input arguments or its JSON result
+        #[arg(long, value_enum, default_value_t = SchemaKind::Input)]
+        kind: SchemaKind,
+    },
+    /// Install embedded skill(s) bundled in the binary
+    InstallSkills {
+        /// Target agent name(s) for agent-specific installation (can be comma-separated or specified multiple times, e.g., --agent claude,opencode or --agent claude --agent opencode)
+        #[arg(long)]
+        agent: Vec<String>,
+        /// Skill name(s) or glob pattern(s) to install (can be comma-separated or specified multiple times, e.g., --skill pdf-*,docx)
+        #[arg(long)]
+        skill: Vec<String>,
+        /// Skill name or glob pattern to leave out (can be comma-separated or specified multiple times)
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Install globally (default: project-local)
+        #[arg(long)]
+        global: bool,
+        /// Skip confirmation prompts
+        #[arg(long)]
+        yes: bool,
+        /// Run in non-interactive mode
+        #[arg(long)]
+        non_interactive: bool,
+        /// Output result as JSON
+        #[arg(long)]
+        json: bool,
+        /// Emit one JSON progress event per line on stdout (ndjson)
+        #[arg(long, value_name = "FORMAT", value_parser = ["ndjson"], conflicts_with = "json")]
+        events: Option<String>,
+        /// Reinstall exactly what the lock file records, failing on any mismatch
+        #[arg(long, visible_alias = "locked", conflicts_with_all = ["skill", "exclude", "events"])]
+        frozen: bool,
+    },
+    /// Install, update and remove project skills to match the skills manifest
+    Sync {
+        /// Path of the skills manifest
+        #[arg(long, value_name = "PATH", default_value = MANIFEST_PATH)]
+        manifest: PathBuf,
+        /// Show the changes without applying them
+        #[arg(long)]
+        dry_run: bool,
+        /// Output result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Inspect and maintain the skills lock file
+    Lock {
+        #[command(subcommand)]
+        command: LockCommands,
+    },
+    /// List the skills of every scope and flag those shadowed by another scope
+    Scopes {
+        /// Output result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Subcommands of `lock`
+#[derive(Debug, Subcommand)]
+pub enum LockCommands {
+    /// Check the lock file and repair recoverable problems
+    Doctor {
+        /// Check the global lock file (default: project-local)
+        #[arg(long)]
+        global: bool,
+        /// Report problems without repairing them
+        #[arg(long)]
+        dry_run: bool,
+        /// Output result as JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+/// Schema selected by `schema --kind`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SchemaKind {
+    /// Arguments accepted by the command
+    Input,
+    /// JSON printed by the command with `--json`
+    Result,
+    /// Lines printed by the command with `--events ndjson`
+    Events,
+    /// The lock file, shared by every command that reads or writes it
+    Lock,
+}
+
+impl Commands {
+    /// Whether the command was asked for machine-readable output
+    pub fn json_output(&self) -> bool {
+        match self {
+            Commands::Commands { output } => output.as_deref() == Some("json"),
+            Commands::Schema { output, .. } => output.as_deref() == Some("json-schema"),
+            Commands::InstallSkills { json, events, .. } => *json || events.is_some(),
+            Commands::Sync { json, .. } => *json,
+            Commands::Lock {
+                command: LockCommands::Doctor { json, .. },
+            } => *json,
+            Commands::Scopes { json } => *json,
+        }
+    }
+
+    /// Whether the command prints `--events ndjson` lines, so errors must be events too
+    pub fn events_output(&self) -> bool {
+        matches!(
+            self,
+            Commands::InstallSkills {
+                events: Some(_),
+                ..
+            }
+        )
+    }
+}
 
 /// CLI command definition
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -18,12 +190,189 @@ pub struct Command {
 pub struct Argument {
     pub name: String,
     pub description: String,
+    /// JSON type of the value: `string`, `boolean`, `integer`, `number` or `array`
     #[serde(rename = "type")]
     pub arg_type: String,
+    /// JSON type of each element when `arg_type` is `array`
+    #[serde(rename = "itemType", skip_serializing_if = "Option::is_none")]
+    pub item_type: Option<String>,
     #[serde(default)]
     pub required: bool,
+    /// Whether the argument can be given more than once
+    #[serde(default)]
+    pub multiple: bool,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub choices: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub default: Option<serde_json::Value>,
+    /// Environment variable the value can be read from
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub env: Option<String>,
+    /// Whether the argument is positional rather than a `--flag`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub positional: bool,
+}
+
+/// Version of the introspection and command result JSON formats
+pub const SCHEMA_VERSION: &str = "1.0";
+
+/// JSON printed by `install-skills --json` on success
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InstallSkillsOutput {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: String,
+    pub ok: bool,
+    pub installed_skills: Vec<InstalledSkill>,
+}
+
+impl InstallSkillsOutput {
+    pub fn new(installed_skills: Vec<InstalledSkill>) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            ok: true,
+            installed_skills,
+        }
+    }
+}
+
+/// JSON printed by `sync --json` on success
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SyncOutput {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: String,
+    pub ok: bool,
+    /// Whether the changes were only planned, not applied
+    pub dry_run: bool,
+    pub installed: Vec<String>,
+    pub updated: Vec<String>,
+    pub removed: Vec<String>,
+    pub unchanged: Vec<String>,
+}
+
+/// JSON printed by `lock doctor --json` on success
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LockDoctorOutput {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: String,
+    pub ok: bool,
+    /// Whether problems were only reported, not repaired
+    pub dry_run: bool,
+    pub fixed: Vec<LockIssue>,
+    pub remaining: Vec<LockIssue>,
+}
+
+/// JSON printed by `scopes --json` on success
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScopesOutput {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: String,
+    pub ok: bool,
+    #[serde(flatten)]
+    pub view: CombinedView,
+}
+
+/// A skill installed by `install-skills`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct InstalledSkill {
+    pub name: String,
+    pub description: String,
+    pub canonical_path: String,
+    pub target_paths: Vec<String>,
+    pub targets: Vec<InstalledTarget>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub symlink_failed: Option<bool>,
+}
+
+/// Per-target install outcome, so symlink failures can be diagnosed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct InstalledTarget {
+    pub path: String,
+    pub requested_mode: InstallMode,
+    pub effective_mode: InstallMode,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fallback_error: Option<String>,
+}
+
+/// JSON envelope printed on failure when machine-readable output was requested
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorEnvelope {
+    pub ok: bool,
+    pub error: ErrorInfo,
+}
+
+/// Machine-readable error details for JSON output
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ErrorInfo {
+    pub code: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hint: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+}
+
+/// One line of `--events ndjson` output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct EventLine<E> {
+    #[serde(rename = "schemaVersion")]
+    pub schema_version: String,
+    #[serde(flatten)]
+    pub event: E,
+}
+
+impl<E> EventLine<E> {
+    pub fn new(event: E) -> Self {
+        Self {
+            schema_version: SCHEMA_VERSION.to_string(),
+            event,
+        }
+    }
+}
+
+/// Progress event emitted by `install-skills --events ndjson`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "event", rename_all = "kebab-case")]
+pub enum InstallEvent {
+    /// Skills found in the source after filtering
+    Discovered { skills: Vec<String> },
+    /// Skill content is being retrieved from its source
+    Fetching { skill: String, source: String },
+    /// Skill was written to its canonical directory
+    Installed { skill: String, path: String },
+    /// Skill was made available in an agent directory
+    Linked {
+        skill: String,
+        #[serde(flatten)]
+        target: InstalledTarget,
+    },
+    /// Lock file entry was written for the skill
+    LockUpdated { skill: String, path: String },
+    /// Something unexpected that didn't stop the install
+    Warning {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        skill: Option<String>,
+        message: String,
+    },
+    /// The install failed; always the last event, with the fields of [`ErrorInfo`]
+    Error {
+        code: String,
+        message: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        hint: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+}
+
+impl From<ErrorInfo> for InstallEvent {
+    fn from(info: ErrorInfo) -> Self {
+        InstallEvent::Error {
+            code: info.code,
+            message: info.message,
+            hint: info.hint,
+            path: info.path,
+        }
+    }
 }
 
 /// Introspection output
@@ -41,7 +390,7 @@ pub struct IntrospectionOutput {
 impl IntrospectionOutput {
     pub fn new(output_type: &str, data: serde_json::Value) -> Self {
         Self {
-            schema_version: "1.0".to_string(),
+            schema_version: SCHEMA_VERSION.to_string(),
             output_type: output_type.to_string(),
             ok: true,
             data,
@@ -51,107 +400,187 @@ impl IntrospectionOutput {
 
 /// Get all commands for introspection
 pub fn get_commands() -> Vec<Command> {
-    vec![
-        Command {
-            name: "install-skills".to_string(),
-            description: "Install embedded skill(s) bundled in the binary".to_string(),
-            subcommands: None,
-            arguments: Some(vec![
-                Argument {
-                    name: "agent".to_string(),
-                    description: "Target agent name(s) for agent-specific installation (can be comma-separated or specified multiple times, e.g., --agent claude,opencode or --agent claude --agent opencode)".to_string(),
-                    arg_type: "string".to_string(),
-                    required: false,
-                    choices: None,
-                },
-                Argument {
-                    name: "skill".to_string(),
-                    description: "Specific skill name to install (if source contains multiple)"
-                        .to_string(),
-                    arg_type: "string".to_string(),
-                    required: false,
-                    choices: None,
-                },
-                Argument {
-                    name: "global".to_string(),
-                    description: "Install globally (default: project-local)".to_string(),
-                    arg_type: "boolean".to_string(),
-                    required: false,
-                    choices: None,
-                },
-                Argument {
-                    name: "yes".to_string(),
-                    description: "Skip confirmation prompts".to_string(),
-                    arg_type: "boolean".to_string(),
-                    required: false,
-                    choices: None,
-                },
-                Argument {
-                    name: "non-interactive".to_string(),
-                    description: "Run in non-interactive mode".to_string(),
-                    arg_type: "boolean".to_string(),
-                    required: false,
-                    choices: None,
-                },
-                Argument {
-                    name: "json".to_string(),
-                    description: "Output result as JSON".to_string(),
-                    arg_type: "boolean".to_string(),
-                    required: false,
-                    choices: None,
-                },
-            ]),
-        },
-        Command {
-            name: "commands".to_string(),
-            description: "List all available commands".to_string(),
-            subcommands: None,
-            arguments: Some(vec![Argument {
-                name: "output".to_string(),
-                description: "Output format (json)".to_string(),
-                arg_type: "string".to_string(),
-                required: false,
-                choices: Some(vec!["json".to_string()]),
-            }]),
-        },
-        Command {
-            name: "schema".to_string(),
-            description: "Get JSON schema for a command".to_string(),
-            subcommands: None,
-            arguments: Some(vec![
-                Argument {
-                    name: "command".to_string(),
-                    description: "Command name to get schema for".to_string(),
-                    arg_type: "string".to_string(),
-                    required: true,
-                    choices: None,
-                },
-                Argument {
-                    name: "output".to_string(),
-                    description: "Output format (json-schema)".to_string(),
-                    arg_type: "string".to_string(),
-                    required: false,
-                    choices: Some(vec!["json-schema".to_string()]),
-                },
-            ]),
-        },
-    ]
+    commands_from_clap(&Cli::command())
+}
+
+/// Build introspection data for every subcommand of a clap command
+pub fn commands_from_clap(cmd: &clap::Command) -> Vec<Command> {
+    // Building propagates settings and fills in implicit defaults (e.g. `false` for flags)
+    let mut cmd = cmd.clone();
+    cmd.build();
+    subcommands_of(&cmd)
+}
+
+fn subcommands_of(cmd: &clap::Command) -> Vec<Command> {
+    cmd.get_subcommands()
+        .filter(|sub| !sub.is_hide_set() && sub.get_name() != "help")
+        .map(command_from_clap)
+        .collect()
+}
+
+fn command_from_clap(cmd: &clap::Command) -> Command {
+    let subcommands = subcommands_of(cmd);
+    let arguments: Vec<Argument> = cmd
+        .get_arguments()
+        .filter(|arg| !arg.is_hide_set() && !is_builtin_arg(arg))
+        .map(argument_from_clap)
+        .collect();
+
+    Command {
+        name: cmd.get_name().to_string(),
+        description: cmd
+            .get_about()
+            .or_else(|| cmd.get_long_about())
+            .map(|about| about.to_string())
+            .unwrap_or_default(),
+        subcommands: (!subcommands.is_empty()).then_some(subcommands),
+        arguments: (!arguments.is_empty()).then_some(arguments),
+    }
+}
+
+fn is_builtin_arg(arg: &clap::Arg) -> bool {
+    matches!(
+        arg.get_action(),
+        ArgAction::Help | ArgAction::HelpShort | ArgAction::HelpLong | ArgAction::Version
+    )
+}
+
+fn argument_from_clap(arg: &clap::Arg) -> Argument {
+    let value_type = value_type(arg);
+    let multiple = matches!(arg.get_action(), ArgAction::Append)
+        || arg.get_num_args().is_some_and(|n| n.max_values() > 1);
+    let arg_type = if multiple { "array" } else { value_type };
+
+    let possible_values: Vec<String> = arg
+        .get_possible_values()
+        .iter()
+        .filter(|v| !v.is_hide_set())
+        .map(|v| v.get_name().to_string())
+        .collect();
+    // Boolean flags report `true`/`false` as possible values; that's implied by the type
+    let choices =
+        (value_type != "boolean" && !possible_values.is_empty()).then_some(possible_values);
+
+    let defaults: Vec<String> = arg
+        .get_default_values()
+        .iter()
+        .map(|v| v.to_string_lossy().to_string())
+        .collect();
+    let default = match defaults.as_slice() {
+        [] => None,
+        values if multiple => Some(json!(values
+            .iter()
+            .map(|v| typed_value(value_type, v))
+            .collect::<Vec<_>>())),
+        [value, ..] => Some(typed_value(value_type, value)),
+    };
+
+    Argument {
+        name: arg
+            .get_long()
+            .map(str::to_string)
+            .unwrap_or_else(|| arg.get_id().to_string()),
+        description: arg
+            .get_help()
+            .or_else(|| arg.get_long_help())
+            .map(|help| help.to_string())
+            .unwrap_or_default(),
+        arg_type: arg_type.to_string(),
+        item_type: multiple.then(|| value_type.to_string()),
+        required: arg.is_required_set(),
+        multiple,
+        choices,
+        default,
+        env: arg.get_env().map(|env| env.to_string_lossy().to_string()),
+        positional: arg.is_positional(),
+    }
+}
+
+/// JSON type of a single value accepted by `arg`
+fn value_type(arg: &clap::Arg) -> &'static str {
+    if matches!(arg.get_action(), ArgAction::SetTrue | ArgAction::SetFalse) {
+        return "boolean";
+    }
+    if matches!(arg.get_action(), ArgAction::Count) {
+        return "integer";
+    }
+
+    let type_id = arg.get_value_parser().type_id();
+    let integers = [
+        TypeId::of::<u8>(),
+        TypeId::of::<u16>(),
+        TypeId::of::<u32>(),
+        TypeId::of::<u64>(),
+        TypeId::of::<usize>(),
+        TypeId::of::<i8>(),
+        TypeId::of::<i16>(),
+        TypeId::of::<i32>(),
+        TypeId::of::<i64>(),
+        TypeId::of::<isize>(),
+    ];
+
+    if type_id == TypeId::of::<bool>() {
+        "boolean"
+    } else if integers.iter().any(|id| type_id == *id) {
+        "integer"
+    } else if type_id == TypeId::of::<f32>() || type_id == TypeId::of::<f64>() {
+        "number"
+    } else {
+        "string"
+    }
+}
+
+/// Convert a default value string into the JSON type of the argument
+fn typed_value(value_type: &str, value: &str) -> serde_json::Value {
+    match value_type {
+        "boolean" => value
+            .parse::<bool>()
+            .map(|v| json!(v))
+            .unwrap_or(json!(value)),
+        "integer" => value
+            .parse::<i64>()
+            .map(|v| json!(v))
+            .unwrap_or(json!(value)),
+        "number" => value
+            .parse::<f64>()
+            .map(|v| json!(v))
+            .unwrap_or(json!(value)),
+        _ => json!(value),
+    }
+}
+
+/// Find a command by name; nested subcommands are addressed as `"parent child"`
+fn find_command<'a>(commands: &'a [Command], name: &str) -> Option<&'a Command> {
+    let mut parts = name.split_whitespace();
+    let first = parts.next()?;
+    let mut current = commands.iter().find(|c| c.name == first)?;
+    for part in parts {
+        current = current
+            .subcommands
+            .as_ref()?
+            .iter()
+            .find(|c| c.name == part)?;
+    }
+    Some(current)
 }
 
 /// Output commands as JSON
 pub fn output_commands_json() -> Result<String> {
     let commands = get_commands();
     let output = IntrospectionOutput::new("commands.list", json!({ "commands": commands }));
-    serde_json::to_string_pretty(&output).context("Failed to serialize commands")
+    serde_json::to_string_pretty(&output).map_err(|source| SkillsError::Serialize {
+        what: "commands",
+        source,
+    })
 }
 
 /// Get JSON schema for a specific command
 pub fn get_command_schema(command_name: &str) -> Result<String> {
     let commands = get_commands();
-    let command = commands
-        .iter()
-        .find(|c| c.name == command_name)
-        .with_context(|| format!("Command not found: {}", command_name))?;
+    let command =
+        find_command(&commands, command_name).ok_or_else(|| SkillsError::CommandNotFound {
+            name: command_name.to_string(),
+        })?;
 
     let mut properties = serde_json::Map::new();
     let mut required = Vec::new();
@@ -162,8 +591,25 @@ pub fn get_command_schema(command_name: &str) -> Result<String> {
             prop.insert("type".to_string(), json!(arg.arg_type));
             prop.insert("description".to_string(), json!(arg.description));
 
+            let mut item = serde_json::Map::new();
+            if let Some(item_type) = &arg.item_type {
+                item.insert("type".to_string(), json!(item_type));
+            }
             if let Some(choices) = &arg.choices {
-                prop.insert("enum".to_string(), json!(choices));
+                if arg.multiple {
+                    item.insert("enum".to_string(), json!(choices));
+                } else {
+                    prop.insert("enum".to_string(), json!(choices));
+                }
+            }
+            if !item.is_empty() {
+                prop.insert("items".to_string(), json!(item));
+            }
+            if let Some(default) = &arg.default {
+                prop.insert("default".to_string(), default.clone());
+            }
+            if let Some(env) = &arg.env {
+                prop.insert("x-env".to_string(), json!(env));
             }
 
             properties.insert(arg.name.clone(), json!(prop));
@@ -183,7 +629,98 @@ pub fn get_command_schema(command_name: &str) -> Result<String> {
     });
 
     let output = IntrospectionOutput::new("schema", json!({ "schema": schema }));
-    serde_json::to_string_pretty(&output).context("Failed to serialize schema")
+    serde_json::to_string_pretty(&output).map_err(|source| SkillsError::Serialize {
+        what: "schema",
+        source,
+    })
+}
+
+/// Get the JSON schema of a command's `--json` result.
+///
+/// The schema accepts either the command's success output or an [`ErrorEnvelope`].
+pub fn get_command_result_schema(command_name: &str) -> Result<String> {
+    let schema = match command_name {
+        "install-skills" => result_schema::<InstallSkillsOutput>(),
+        "sync" => result_schema::<SyncOutput>(),
+        "lock doctor" => result_schema::<LockDoctorOutput>(),
+        "scopes" => result_schema::<ScopesOutput>(),
+        _ => return Err(schema_not_found(command_name, "result")),
+    };
+
+    let output = IntrospectionOutput::new(
+        "schema.result",
+        json!({ "command": command_name, "schema": schema }),
+    );
+    serde_json::to_string_pretty(&output).map_err(|source| SkillsError::Serialize {
+        what: "result schema",
+        source,
+    })
+}
+
+/// Get the JSON schema of each line a command prints with `--events ndjson`
+pub fn get_command_events_schema(command_name: &str) -> Result<String> {
+    let schema = match command_name {
+        "install-skills" => {
+            let schema = SchemaSettings::draft07()
+                .into_generator()
+                .into_root_schema_for::<EventLine<InstallEvent>>();
+            serde_json::to_value(schema).map_err(|source| SkillsError::Serialize {
+                what: "events schema",
+                source,
+            })?
+        }
+        _ => return Err(schema_not_found(command_name, "events")),
+    };
+
+    let output = IntrospectionOutput::new(
+        "schema.events",
+        json!({ "command": command_name, "schema": schema }),
+    );
+    serde_json::to_string_pretty(&output).map_err(|source| SkillsError::Serialize {
+        what: "events schema",
+        source,
+    })
+}
+
+/// Get the JSON schema of the lock file
+pub fn get_lock_schema() -> Result<String> {
+    let schema = SchemaSettings::draft07()
+        .into_generator()
+        .into_root_schema_for::<SkillLock>();
+    let schema = serde_json::to_value(schema).map_err(|source| SkillsError::Serialize {
+        what: "lock schema",
+        source,
+    })?;
+
+    let output = IntrospectionOutput::new("schema.lock", json!({ "schema": schema }));
+    serde_json::to_string_pretty(&output).map_err(|source| SkillsError::Serialize {
+        what: "lock schema",
+        source,
+    })
+}
+
+fn schema_not_found(command_name: &str, kind: &'static str) -> SkillsError {
+    if find_command(&get_commands(), command_name).is_some() {
+        SkillsError::SchemaNotFound {
+            name: command_name.to_string(),
+            kind,
+        }
+    } else {
+        SkillsError::CommandNotFound {
+            name: command_name.to_string(),
+        }
+    }
+}
+
+fn result_schema<T: JsonSchema>() -> serde_json::Value {
+    let mut generator = SchemaSettings::draft07().into_generator();
+    let success = generator.subschema_for::<T>();
+    let failure = generator.subschema_for::<ErrorEnvelope>();
+    json!({
+        "$schema": "http://json-schema.org/draft-07/schema#",
+        "oneOf": [success, failure],
+        "definitions": generator.definitions(),
+    })
 }
 
 #[cfg(test)]
@@ -267,7 +804,7 @@ mod tests {
         );
         assert_eq!(
             parsed["schema"]["properties"]["skill"]["description"],
-            "Specific skill name to install (if source contains multiple)"
+            "Skill name(s) or glob pattern(s) to install (can be comma-separated or specified multiple times, e.g., --skill pdf-*,docx)"
         );
         assert_eq!(
             parsed["schema"]["properties"]["global"]["description"],
@@ -279,9 +816,279 @@ mod tests {
     fn test_get_command_schema_not_found() {
         let result = get_command_schema("nonexistent");
         assert!(result.is_err());
-        assert!(result
-            .unwrap_err()
-            .to_string()
-            .contains("Command not found"));
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), "command_not_found");
+        assert!(err.to_string().contains("Command not found"));
+    }
+
+    #[test]
+    fn test_commands_match_clap_definition() {
+        let cli = Cli::command();
+        let names: Vec<_> = get_commands().into_iter().map(|c| c.name).collect();
+        let clap_names: Vec<_> = cli
+            .get_subcommands()
+            .map(|c| c.get_name().to_string())
+            .collect();
+        assert_eq!(names, clap_names);
+        assert!(!names.contains(&"help".to_string()));
+    }
+
+    #[test]
+    fn test_agent_argument_is_array() {
+        let schema = get_command_schema("install-skills").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+
+        let agent = &parsed["schema"]["properties"]["agent"];
+        assert_eq!(agent["type"], "array");
+        assert_eq!(agent["items"]["type"], "string");
+        assert_eq!(parsed["schema"]["properties"]["global"]["type"], "boolean");
+        assert_eq!(parsed["schema"]["properties"]["global"]["default"], false);
+        assert_eq!(parsed["schema"]["properties"]["skill"]["type"], "array");
+        assert_eq!(parsed["schema"]["properties"]["exclude"]["type"], "array");
+        assert!(parsed["schema"]["properties"]["help"].is_null());
+    }
+
+    #[test]
+    fn test_schema_command_has_required_argument_and_choices() {
+        let commands = get_commands();
+        let schema_cmd = commands.iter().find(|c| c.name == "schema").unwrap();
+        let args = schema_cmd.arguments.as_ref().unwrap();
+
+        // Not needed for --kind lock
+        let command_arg = args.iter().find(|a| a.name == "command").unwrap();
+        assert!(!command_arg.required);
+
+        let output_arg = args.iter().find(|a| a.name == "output").unwrap();
+        assert_eq!(output_arg.choices, Some(vec!["json-schema".to_string()]));
+
+        let kind_arg = args.iter().find(|a| a.name == "kind").unwrap();
+        assert_eq!(
+            kind_arg.choices,
+            Some(vec![
+                "input".to_string(),
+                "result".to_string(),
+                "events".to_string(),
+                "lock".to_string()
+            ])
+        );
+        assert_eq!(kind_arg.default, Some(json!("input")));
+    }
+
+    #[test]
+    fn test_get_command_result_schema() {
+        let schema = get_command_result_schema("install-skills").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+
+        assert_eq!(parsed["schemaVersion"], SCHEMA_VERSION);
+        assert_eq!(parsed["type"], "schema.result");
+        assert_eq!(parsed["command"], "install-skills");
+
+        let schema = &parsed["schema"];
+        assert_eq!(schema["oneOf"].as_array().unwrap().len(), 2);
+        let output = &schema["definitions"]["InstallSkillsOutput"];
+        assert!(output["properties"]["schemaVersion"].is_object());
+        assert!(output["properties"]["installed_skills"].is_object());
+        let target = &schema["definitions"]["InstalledTarget"];
+        assert!(target["properties"]["effective_mode"].is_object());
+        assert!(schema["definitions"]["InstallMode"].is_object());
+        assert!(schema["definitions"]["ErrorInfo"]["properties"]["code"].is_object());
+    }
+
+    #[test]
+    fn test_get_command_events_schema() {
+        let schema = get_command_events_schema("install-skills").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        assert_eq!(parsed["type"], "schema.events");
+
+        let text = parsed["schema"].to_string();
+        for event in [
+            "discovered",
+            "fetching",
+            "installed",
+            "linked",
+            "lock-updated",
+            "warning",
+            "error",
+        ] {
+            assert!(
+                text.contains(&format!("\"{}\"", event)),
+                "missing {}",
+                event
+            );
+        }
+        assert!(text.contains("schemaVersion"));
+
+        let err = get_command_events_schema("commands").unwrap_err();
+        assert_eq!(err.code(), "schema_not_found");
+        assert_eq!(err.to_string(), "No events schema for command: commands");
+    }
+
+    #[test]
+    fn test_event_line_json() {
+        let line = EventLine::new(InstallEvent::Linked {
+            skill: "pdf".to_string(),
+            target: InstalledTarget {
+                path: "/p/.claude/skills/pdf".to_string(),
+                requested_mode: InstallMode::Symlink,
+                effective_mode: InstallMode::Symlink,
+                fallback_error: None,
+            },
+        });
+        let json = serde_json::to_value(&line).unwrap();
+        assert_eq!(
+            json,
+            json!({
+                "schemaVersion": SCHEMA_VERSION,
+                "event": "linked",
+                "skill": "pdf",
+                "path": "/p/.claude/skills/pdf",
+                "requested_mode": "symlink",
+                "effective_mode": "symlink",
+            })
+        );
+
+        let parsed: EventLine<InstallEvent> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, line);
+
+        let line = EventLine::new(InstallEvent::from(ErrorInfo {
+            code: "unsafe_path".to_string(),
+            message: "Refusing to write".to_string(),
+            hint: None,
+            path: Some("/p/.agents/skills/pdf".to_string()),
+        }));
+        assert_eq!(
+            serde_json::to_value(&line).unwrap(),
+            json!({
+                "schemaVersion": SCHEMA_VERSION,
+                "event": "error",
+                "code": "unsafe_path",
+                "message": "Refusing to write",
+                "path": "/p/.agents/skills/pdf",
+            })
+        );
+    }
+
+    #[test]
+    fn test_sync_command_and_result_schema() {
+        let commands = get_commands();
+        let sync = commands.iter().find(|c| c.name == "sync").unwrap();
+        let manifest = sync
+            .arguments
+            .as_ref()
+            .unwrap()
+            .iter()
+            .find(|a| a.name == "manifest")
+            .unwrap();
+        assert_eq!(manifest.default, Some(json!(MANIFEST_PATH)));
+
+        let schema = get_command_result_schema("sync").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        let output = &parsed["schema"]["definitions"]["SyncOutput"];
+        for field in ["installed", "updated", "removed", "unchanged", "dry_run"] {
+            assert!(output["properties"][field].is_object(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_lock_schema_and_doctor_command() {
+        let schema = get_lock_schema().unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        assert_eq!(parsed["type"], "schema.lock");
+        assert!(parsed.get("command").is_none());
+        let schema = &parsed["schema"];
+        assert_eq!(schema["additionalProperties"], false);
+        // The loader accepts both, so the schema does too
+        assert_eq!(
+            schema["properties"]["version"]["$ref"],
+            "#/definitions/LockVersion"
+        );
+        let version_types: Vec<_> = schema["definitions"]["LockVersion"]["anyOf"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|s| s["type"].as_str().unwrap())
+            .collect();
+        assert_eq!(version_types, vec!["string", "integer"]);
+        let entry = &schema["definitions"]["LockEntry"];
+        assert_eq!(entry["additionalProperties"], false);
+        for field in [
+            "sourceType",
+            "skillFolderHash",
+            "installedAt",
+            "ref",
+            "history",
+        ] {
+            assert!(entry["properties"][field].is_object(), "{}", field);
+        }
+
+        let schema = get_command_result_schema("lock doctor").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        assert!(parsed["schema"]["definitions"]["LockDoctorOutput"].is_object());
+        assert!(get_command_schema("lock doctor").is_ok());
+
+        let schema = get_command_result_schema("scopes").unwrap();
+        let parsed: serde_json::Value = serde_json::from_str(&schema).unwrap();
+        let output = &parsed["schema"]["definitions"]["ScopesOutput"];
+        assert!(output["properties"]["skills"].is_object());
+        assert!(parsed["schema"]["definitions"]["ScopedSkill"].is_object());
+    }
+
+    #[test]
+    fn test_result_schema_errors() {
+        let err = get_command_result_schema("commands").unwrap_err();
+        assert_eq!(err.code(), "schema_not_found");
+        let err = get_command_result_schema("nonexistent").unwrap_err();
+        assert_eq!(err.code(), "command_not_found");
+    }
+
+    #[test]
+    fn test_install_output_matches_schema_fields() {
+        let output = InstallSkillsOutput::new(vec![]);
+        let json = serde_json::to_value(&output).unwrap();
+        assert_eq!(json["schemaVersion"], SCHEMA_VERSION);
+        assert_eq!(json["ok"], true);
+        assert_eq!(json["installed_skills"], json!([]));
+    }
+
+    #[test]
+    fn test_commands_from_custom_clap_command() {
+        let cmd = clap::Command::new("tool").subcommand(
+            clap::Command::new("run")
+                .about("Run things")
+                .arg(
+                    clap::Arg::new("jobs")
+                        .long("jobs")
+                        .help("Parallel jobs")
+                        .value_parser(clap::value_parser!(u32))
+                        .default_value("4")
+                        .env("TOOL_JOBS"),
+                )
+                .arg(
+                    clap::Arg::new("mode")
+                        .long("mode")
+                        .value_parser(["fast", "slow"]),
+                )
+                .subcommand(clap::Command::new("nested").about("Nested command")),
+        );
+
+        let commands = commands_from_clap(&cmd);
+        assert_eq!(commands.len(), 1);
+        let run = &commands[0];
+        assert_eq!(run.description, "Run things");
+
+        let args = run.arguments.as_ref().unwrap();
+        let jobs = args.iter().find(|a| a.name == "jobs").unwrap();
+        assert_eq!(jobs.arg_type, "integer");
+        assert_eq!(jobs.default, Some(json!(4)));
+        assert_eq!(jobs.env.as_deref(), Some("TOOL_JOBS"));
+
+        let mode = args.iter().find(|a| a.name == "mode").unwrap();
+        assert_eq!(
+            mode.choices,
+            Some(vec!["fast".to_string(), "slow".to_string()])
+        );
+
+        let nested = find_command(&commands, "run nested").unwrap();
+        assert_eq!(nested.description, "Nested command");
     }
 }
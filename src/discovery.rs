@@ -1,21 +1,95 @@
 use crate::embedded;
+use crate::error::{Result, SkillsError};
 use crate::providers::SkillProvider;
-use crate::types::{Skill, SkillMetadata, Source};
-use anyhow::{Context, Result};
+use crate::types::{validate_skill_name, Skill, SkillMetadata, Source};
+use ignore::gitignore::Gitignore;
+use ignore::WalkBuilder;
 use serde_yaml::Value as YamlValue;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
 
 #[cfg(test)]
 use crate::types::SourceType;
 
+/// Directories searched for skills before falling back to a recursive search, in priority order
+pub const DEFAULT_SEARCH_ROOTS: &[&str] = &[
+    "skills",
+    "skills/.curated",
+    "skills/.experimental",
+    "skills/.system",
+    ".agents/skills",
+    ".agent/skills",
+    ".claude/skills",
+    ".codex/skills",
+    ".github/skills",
+    ".opencode/skills",
+    ".config/opencode/skills",
+];
+
+/// Directory names never descended into during discovery
+pub const DEFAULT_EXCLUDED_DIRS: &[&str] = &["node_modules", "target", ".git"];
+
+/// Ignore file read alongside `.gitignore` during discovery
+pub const SKILLS_IGNORE_FILE: &str = ".skillsignore";
+
+/// What to do when discovery finds several skills with the same name
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Keep the skill found first in priority order and drop the others
+    #[default]
+    FirstWins,
+    /// Fail with [`SkillsError::DuplicateSkill`]
+    Error,
+    /// Keep all of them, renaming later ones with a namespace prefix
+    Rename,
+}
+
+/// A skill whose name collided with one found earlier
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateSkill {
+    pub name: String,
+    /// SKILL.md of the skill that kept the name
+    pub kept: Option<String>,
+    /// SKILL.md of the colliding skill
+    pub duplicate: Option<String>,
+    /// New name of the colliding skill under [`DuplicatePolicy::Rename`]
+    pub renamed_to: Option<String>,
+}
+
+/// A SKILL.md that was skipped because it doesn't parse or validate
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSkill {
+    pub path: String,
+    pub reason: String,
+}
+
+/// Discovered skills together with the name collisions that were resolved and the
+/// SKILL.md files that were skipped
+#[derive(Debug, Clone, Default)]
+pub struct DiscoveryReport {
+    pub skills: Vec<Skill>,
+    pub duplicates: Vec<DuplicateSkill>,
+    pub invalid: Vec<InvalidSkill>,
+}
+
 /// Configuration for skill discovery
 #[derive(Debug, Clone)]
 pub struct DiscoveryConfig {
     pub allow_internal: bool,
+    /// How many directory levels below a search root a SKILL.md may be.
+    ///
+    /// `0` only finds `<root>/SKILL.md`, `1` also finds `<root>/<skill>/SKILL.md`, and so on.
+    /// Each search root (and the recursive fallback from the source root) counts from itself.
     pub max_depth: usize,
+    /// Directories searched first, relative to the source root, in priority order
+    pub search_roots: Vec<PathBuf>,
+    /// Directory names skipped during search
+    pub excluded_dirs: Vec<String>,
+    /// Honour `.gitignore` and `.skillsignore` files found while searching
+    pub use_ignore_files: bool,
+    /// How to handle several skills with the same name
+    pub on_duplicate: DuplicatePolicy,
 }
 
 impl Default for DiscoveryConfig {
@@ -25,6 +99,13 @@ impl Default for DiscoveryConfig {
                 .map(|v| v == "1")
                 .unwrap_or(false),
             max_depth: 3,
+            search_roots: DEFAULT_SEARCH_ROOTS.iter().map(PathBuf::from).collect(),
+            excluded_dirs: DEFAULT_EXCLUDED_DIRS
+                .iter()
+                .map(|d| d.to_string())
+                .collect(),
+            use_ignore_files: true,
+            on_duplicate: DuplicatePolicy::default(),
         }
     }
 }
@@ -34,15 +115,58 @@ pub fn discover_skills(source: &Source, config: &DiscoveryConfig) -> Result<Vec<
     discover_skills_with_provider(source, config, None)
 }
 
-/// Discover skills with an optional provider (for testing and external sources)
+/// Discover skills with an optional provider (for testing and external sources).
+///
+/// Only skills selected by `source.skill_filter` are returned, so callers never
+/// fetch skills that weren't asked for. Skills are sorted by name, then path.
 pub fn discover_skills_with_provider(
     source: &Source,
     config: &DiscoveryConfig,
     provider: Option<&dyn SkillProvider>,
 ) -> Result<Vec<Skill>> {
+    discover_skills_with_report(source, config, provider).map(|report| report.skills)
+}
+
+/// Discover skills and report how name collisions were resolved.
+///
+/// Collisions are resolved in priority order (search root order for local sources,
+/// provider order otherwise) according to [`DiscoveryConfig::on_duplicate`].
+pub fn discover_skills_with_report(
+    source: &Source,
+    config: &DiscoveryConfig,
+    provider: Option<&dyn SkillProvider>,
+) -> Result<DiscoveryReport> {
+    let mut invalid = Vec::new();
+    let skills = discover_selected_skills(source, config, provider, &mut invalid)?;
+    let (mut skills, duplicates) = resolve_duplicates(skills, config.on_duplicate)?;
+    sort_skills(&mut skills);
+    Ok(DiscoveryReport {
+        skills,
+        duplicates,
+        invalid,
+    })
+}
+
+/// Discover the skills selected by the source's filter, in priority order, adding the
+/// SKILL.md files that had to be skipped to `invalid`
+fn discover_selected_skills(
+    source: &Source,
+    config: &DiscoveryConfig,
+    provider: Option<&dyn SkillProvider>,
+    invalid: &mut Vec<InvalidSkill>,
+) -> Result<Vec<Skill>> {
+    let selected = |skill: &Skill| {
+        source
+            .skill_filter
+            .as_ref()
+            .is_none_or(|filter| filter.matches(&skill.name))
+    };
+
     // Handle embedded sources
     if source.source_type.is_embedded() {
-        return discover_embedded_skills(config);
+        let mut skills = discover_embedded_skills(config)?;
+        skills.retain(selected);
+        return Ok(skills);
     }
 
     // For local sources, perform file system discovery
@@ -52,7 +176,13 @@ pub fn discover_skills_with_provider(
             .as_ref()
             .map(PathBuf::from)
             .unwrap_or_else(|| PathBuf::from("."));
-        return discover_local_skills(&base_path, config);
+        let base_path = match &source.subpath {
+            Some(subpath) => base_path.join(subpath),
+            None => base_path,
+        };
+        let mut skills = discover_local_skills(&base_path, config, invalid)?;
+        skills.retain(selected);
+        return Ok(skills);
     }
 
     // For other source types (github, gitlab, direct), use provider if available
@@ -60,14 +190,30 @@ pub fn discover_skills_with_provider(
         let url = source
             .url
             .as_ref()
-            .ok_or_else(|| anyhow::anyhow!("URL required for provider-based discovery"))?;
-        let skills = provider.discover_skills(url, source.subpath.as_deref())?;
-
-        // Apply internal filter
+            .ok_or_else(|| SkillsError::InvalidSource {
+                reason: "URL required for provider-based discovery".to_string(),
+            })?;
+        let skills = provider
+            .discover_skills_matching(
+                url,
+                source.subpath.as_deref(),
+                source.ref_.as_deref(),
+                source.skill_filter.as_ref(),
+            )
+            .map_err(|error| SkillsError::Provider {
+                context: format!("Failed to discover skills from {}", url),
+                error,
+            })?;
+
+        // Providers may not apply the name filter themselves
         let filtered: Vec<Skill> = skills
             .into_iter()
             .filter(|s| config.allow_internal || !s.metadata.internal)
+            .filter(selected)
             .collect();
+        for skill in &filtered {
+            validate_skill_name(&skill.name)?;
+        }
 
         return Ok(filtered);
     }
@@ -76,56 +222,274 @@ pub fn discover_skills_with_provider(
     Ok(Vec::new())
 }
 
+/// Find skills that share a name with an earlier skill in the list
+pub fn find_duplicate_skills(skills: &[Skill]) -> Vec<DuplicateSkill> {
+    let mut first_by_name: HashMap<&str, &Skill> = HashMap::new();
+    let mut duplicates = Vec::new();
+    for skill in skills {
+        match first_by_name.get(skill.name.as_str()) {
+            Some(first) => duplicates.push(DuplicateSkill {
+                name: skill.name.clone(),
+                kept: first.path.clone(),
+                duplicate: skill.path.clone(),
+                renamed_to: None,
+            }),
+            None => {
+                first_by_name.insert(&skill.name, skill);
+            }
+        }
+    }
+    duplicates
+}
+
+/// Apply the duplicate policy to skills listed in priority order
+fn resolve_duplicates(
+    skills: Vec<Skill>,
+    policy: DuplicatePolicy,
+) -> Result<(Vec<Skill>, Vec<DuplicateSkill>)> {
+    let duplicates = find_duplicate_skills(&skills);
+    if duplicates.is_empty() {
+        return Ok((skills, duplicates));
+    }
+
+    match policy {
+        DuplicatePolicy::Error => {
+            let duplicate = &duplicates[0];
+            Err(SkillsError::DuplicateSkill {
+                name: duplicate.name.clone(),
+                first: duplicate.kept.clone().map(PathBuf::from),
+                second: duplicate.duplicate.clone().map(PathBuf::from),
+            })
+        }
+        DuplicatePolicy::FirstWins => {
+            let mut seen = HashSet::new();
+            let kept = skills
+                .into_iter()
+                .filter(|skill| seen.insert(skill.name.clone()))
+                .collect();
+            Ok((kept, duplicates))
+        }
+        DuplicatePolicy::Rename => {
+            let mut taken: HashSet<String> = skills.iter().map(|s| s.name.clone()).collect();
+            let mut seen = HashSet::new();
+            let mut report = Vec::new();
+            let mut kept = Vec::with_capacity(skills.len());
+            for mut skill in skills {
+                if !seen.insert(skill.name.clone()) {
+                    let new_name = namespaced_name(&skill, &taken);
+                    report.push(DuplicateSkill {
+                        name: skill.name.clone(),
+                        kept: kept
+                            .iter()
+                            .find(|k: &&Skill| k.name == skill.name)
+                            .and_then(|k| k.path.clone()),
+                        duplicate: skill.path.clone(),
+                        renamed_to: Some(new_name.clone()),
+                    });
+                    skill.raw_content = rename_in_frontmatter(&skill.raw_content, &new_name);
+                    skill.name = new_name.clone();
+                    taken.insert(new_name);
+                }
+                kept.push(skill);
+            }
+            Ok((kept, report))
+        }
+    }
+}
+
+/// Pick a new name for a duplicate skill, prefixed with the directory it was found under
+/// (e.g. `claude-pdf` for `.claude/skills/pdf`), or numbered when that doesn't work
+fn namespaced_name(skill: &Skill, taken: &HashSet<String>) -> String {
+    let namespace = skill.path.as_deref().and_then(|path| {
+        Path::new(path)
+            .parent()?
+            .parent()?
+            .components()
+            .rev()
+            .filter_map(|c| c.as_os_str().to_str())
+            .map(sanitize_name)
+            .find(|n| !n.is_empty() && n != "skills")
+    });
+    if let Some(namespace) = namespace {
+        let name = format!("{}-{}", namespace, skill.name);
+        if !taken.contains(&name) && validate_skill_name(&name).is_ok() {
+            return name;
+        }
+    }
+    (2..)
+        .map(|n| format!("{}-{}", skill.name, n))
+        .find(|name| !taken.contains(name) && validate_skill_name(name).is_ok())
+        .unwrap_or_else(|| skill.name.clone())
+}
+
+/// Turn a directory name into something usable as a skill name component
+fn sanitize_name(raw: &str) -> String {
+    let mut name = String::new();
+    for c in raw.chars() {
+        if c.is_ascii_alphanumeric() {
+            name.push(c.to_ascii_lowercase());
+        } else if !name.is_empty() && !name.ends_with('-') {
+            name.push('-');
+        }
+    }
+    name.trim_end_matches('-').to_string()
+}
+
+/// Replace the `name:` field in a SKILL.md frontmatter
+pub(crate) fn rename_in_frontmatter(content: &str, new_name: &str) -> String {
+    let mut in_frontmatter = false;
+    let mut renamed = false;
+    let mut lines = Vec::new();
+    for (i, line) in content.split('\n').enumerate() {
+        if line.trim() == "---" {
+            in_frontmatter = i == 0;
+        } else if in_frontmatter && !renamed && line.starts_with("name:") {
+            lines.push(format!("name: {}", new_name));
+            renamed = true;
+            continue;
+        }
+        lines.push(line.to_string());
+    }
+    lines.join("\n")
+}
+
+/// Order skills by name, then path, so discovery output doesn't depend on traversal order
+fn sort_skills(skills: &mut [Skill]) {
+    skills.sort_by(|a, b| a.name.cmp(&b.name).then_with(|| a.path.cmp(&b.path)));
+}
+
 /// Discover embedded skills
 fn discover_embedded_skills(_config: &DiscoveryConfig) -> Result<Vec<Skill>> {
-    embedded::get_embedded_skills().context("Failed to load embedded skills")
+    embedded::get_embedded_skills()
 }
 
 /// Discover skills from local file system
-fn discover_local_skills(base_path: &Path, config: &DiscoveryConfig) -> Result<Vec<Skill>> {
+fn discover_local_skills(
+    base_path: &Path,
+    config: &DiscoveryConfig,
+    invalid: &mut Vec<InvalidSkill>,
+) -> Result<Vec<Skill>> {
     let mut skills = Vec::new();
-
-    // Priority search directories
-    let priority_dirs = vec![
-        "skills",
-        ".agents/skills",
-        ".claude/skills",
-        ".config/opencode/skills",
-    ];
-
-    // First try priority directories
-    for dir in &priority_dirs {
-        let search_path = base_path.join(dir);
-        if search_path.exists() {
-            skills.extend(search_directory(&search_path, config, 0)?);
-        }
+    let mut seen = HashSet::new();
+
+    // First try the configured search roots
+    let roots: Vec<PathBuf> = config
+        .search_roots
+        .iter()
+        .map(|root| base_path.join(root))
+        .filter(|root| root.is_dir())
+        .collect();
+    for root in &roots {
+        // Roots nested in this one (e.g. `skills/.curated` in `skills`) are searched on their own
+        let nested: Vec<PathBuf> = roots
+            .iter()
+            .filter(|other| *other != root && other.starts_with(root))
+            .cloned()
+            .collect();
+        push_unique(
+            &mut skills,
+            &mut seen,
+            search_directory(root, base_path, config, &nested, invalid)?,
+        );
     }
 
-    // If no skills found and max_depth allows, do recursive search
-    if skills.is_empty() && config.max_depth > 0 {
-        skills.extend(search_directory(base_path, config, 0)?);
+    // If no skills found, search the whole source root
+    if skills.is_empty() {
+        push_unique(
+            &mut skills,
+            &mut seen,
+            search_directory(base_path, base_path, config, &[], invalid)?,
+        );
     }
 
+    // The fallback search revisits the roots, and symlinks may reach a file twice
+    let mut seen_invalid = HashSet::new();
+    invalid.retain(|skill| {
+        seen_invalid
+            .insert(fs::canonicalize(&skill.path).unwrap_or_else(|_| skill.path.clone().into()))
+    });
+
     Ok(skills)
 }
 
-/// Search a directory for SKILL.md files
-fn search_directory(dir: &Path, config: &DiscoveryConfig, depth: usize) -> Result<Vec<Skill>> {
-    if depth > config.max_depth {
-        return Ok(Vec::new());
+/// Add skills whose SKILL.md hasn't been seen yet, comparing canonical paths so
+/// files reached through symlinks or overlapping roots are only reported once
+fn push_unique(skills: &mut Vec<Skill>, seen: &mut HashSet<PathBuf>, found: Vec<Skill>) {
+    for skill in found {
+        let key = skill
+            .path
+            .as_deref()
+            .map(|p| fs::canonicalize(p).unwrap_or_else(|_| PathBuf::from(p)))
+            .unwrap_or_default();
+        if seen.insert(key) {
+            skills.push(skill);
+        }
     }
+}
 
+/// Search a directory for SKILL.md files, skipping excluded, ignored and `skip` directories.
+/// SKILL.md files that don't parse or validate are added to `invalid`.
+///
+/// Ignore files in the directories between the source's `base_path` and `dir` apply too,
+/// e.g. a repository's root `.gitignore` when searching its `skills` directory.
+fn search_directory(
+    dir: &Path,
+    base_path: &Path,
+    config: &DiscoveryConfig,
+    skip: &[PathBuf],
+    invalid: &mut Vec<InvalidSkill>,
+) -> Result<Vec<Skill>> {
     let mut skills = Vec::new();
 
-    for entry in WalkDir::new(dir)
-        .max_depth(config.max_depth - depth)
-        .into_iter()
-        .filter_map(|e| e.ok())
-    {
+    let excluded_dirs = config.excluded_dirs.clone();
+    let skip = skip.to_vec();
+    let parent_ignores = if config.use_ignore_files {
+        parent_ignore_files(dir, base_path)
+    } else {
+        Vec::new()
+    };
+    let mut walker = WalkBuilder::new(dir);
+    walker
+        // SKILL.md sits one level below the deepest directory searched
+        .max_depth(Some(config.max_depth.saturating_add(1)))
+        .follow_links(true)
+        .standard_filters(false)
+        .git_ignore(config.use_ignore_files)
+        .require_git(false)
+        .filter_entry(move |entry| {
+            let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+            let excluded = excluded_dirs
+                .iter()
+                .any(|name| entry.file_name() == name.as_str());
+            // The walker reads ignore files from `dir` down; apply those above it here,
+            // nearest first
+            let ignored = entry.depth() > 0
+                && parent_ignores
+                    .iter()
+                    .map(|ignore| ignore.matched(entry.path(), is_dir))
+                    .find(|m| !m.is_none())
+                    .is_some_and(|m| m.is_ignore());
+            !ignored
+                && !(is_dir
+                    && entry.depth() > 0
+                    && (excluded || skip.iter().any(|s| s == entry.path())))
+        });
+    if config.use_ignore_files {
+        walker.add_custom_ignore_filename(SKILLS_IGNORE_FILE);
+    }
+
+    for entry in walker.build().filter_map(|e| e.ok()) {
         let path = entry.path();
         if path.file_name() == Some(std::ffi::OsStr::new("SKILL.md")) {
-            if let Ok(skill) = parse_skill_file(path, config) {
-                skills.push(skill);
+            match parse_skill_file(path) {
+                Ok(skill) if config.allow_internal || !skill.metadata.internal => {
+                    skills.push(skill)
+                }
+                Ok(_) => {}
+                Err(error) => invalid.push(InvalidSkill {
+                    path: path.display().to_string(),
+                    reason: error.to_string(),
+                }),
             }
         }
     }
@@ -133,11 +497,41 @@ fn search_directory(dir: &Path, config: &DiscoveryConfig, depth: usize) -> Resul
     Ok(skills)
 }
 
+/// Ignore files of the directories between `base_path` and `dir`, nearest to `dir` first.
+/// Unreadable files and invalid patterns are skipped, as the walker does.
+fn parent_ignore_files(dir: &Path, base_path: &Path) -> Vec<Gitignore> {
+    let mut ignores = Vec::new();
+    for parent in dir
+        .ancestors()
+        .skip(1)
+        .take_while(|parent| parent.starts_with(base_path))
+    {
+        // Within a directory, `.skillsignore` takes precedence like it does for the walker
+        for name in [SKILLS_IGNORE_FILE, ".gitignore"] {
+            let ignore_file = parent.join(name);
+            if ignore_file.is_file() {
+                ignores.push(Gitignore::new(&ignore_file).0);
+            }
+        }
+    }
+    ignores
+}
+
 /// Parse a SKILL.md file
-fn parse_skill_file(path: &Path, config: &DiscoveryConfig) -> Result<Skill> {
-    let content = fs::read_to_string(path)
-        .with_context(|| format!("Failed to read SKILL.md at {:?}", path))?;
+fn parse_skill_file(path: &Path) -> Result<Skill> {
+    let content = fs::read_to_string(path).map_err(SkillsError::io("read SKILL.md", path))?;
+
+    parse_skill_content(path, content).map_err(|e| match e {
+        SkillsError::InvalidSkill { path: None, reason } => SkillsError::InvalidSkill {
+            path: Some(path.to_path_buf()),
+            reason,
+        },
+        other => other,
+    })
+}
 
+/// Build a skill from the content of the SKILL.md at `path`
+fn parse_skill_content(path: &Path, content: String) -> Result<Skill> {
     // Parse frontmatter
     let (frontmatter, _body) = parse_frontmatter(&content)?;
 
@@ -145,13 +539,14 @@ fn parse_skill_file(path: &Path, config: &DiscoveryConfig) -> Result<Skill> {
     let name = frontmatter
         .get("name")
         .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Missing 'name' in frontmatter"))?
+        .ok_or_else(|| SkillsError::invalid_skill("Missing 'name' in frontmatter"))?
         .to_string();
+    validate_skill_name(&name)?;
 
     let description = frontmatter
         .get("description")
         .and_then(|v| v.as_str())
-        .ok_or_else(|| anyhow::anyhow!("Missing 'description' in frontmatter"))?
+        .ok_or_else(|| SkillsError::invalid_skill("Missing 'description' in frontmatter"))?
         .to_string();
 
     // Extract metadata
@@ -172,11 +567,6 @@ fn parse_skill_file(path: &Path, config: &DiscoveryConfig) -> Result<Skill> {
 
     metadata.internal = internal_flag;
 
-    // Filter internal skills if not allowed
-    if metadata.internal && !config.allow_internal {
-        anyhow::bail!("Internal skill not allowed");
-    }
-
     // Store other frontmatter fields in extra
     for (key, value) in frontmatter {
         if key != "name" && key != "description" && key != "internal" {
@@ -201,7 +591,7 @@ fn parse_frontmatter(content: &str) -> Result<(HashMap<String, YamlValue>, Strin
     let lines: Vec<&str> = content.lines().collect();
 
     if lines.is_empty() || lines[0] != "---" {
-        anyhow::bail!("Missing frontmatter delimiter");
+        return Err(SkillsError::invalid_skill("Missing frontmatter delimiter"));
     }
 
     let mut frontmatter_end = None;
@@ -212,11 +602,14 @@ fn parse_frontmatter(content: &str) -> Result<(HashMap<String, YamlValue>, Strin
         }
     }
 
-    let frontmatter_end = frontmatter_end.ok_or_else(|| anyhow::anyhow!("Unclosed frontmatter"))?;
+    let frontmatter_end =
+        frontmatter_end.ok_or_else(|| SkillsError::invalid_skill("Unclosed frontmatter"))?;
 
     let frontmatter_str = lines[1..frontmatter_end].join("\n");
     let frontmatter: HashMap<String, YamlValue> =
-        serde_yaml::from_str(&frontmatter_str).context("Failed to parse frontmatter YAML")?;
+        serde_yaml::from_str(&frontmatter_str).map_err(|e| {
+            SkillsError::invalid_skill(format!("Failed to parse frontmatter YAML: {}", e))
+        })?;
 
     let body = lines[(frontmatter_end + 1)..].join("\n");
 
@@ -318,7 +711,7 @@ description: A test skill
         fs::write(skills_dir.join("SKILL.md"), skill_content).unwrap();
 
         let config = DiscoveryConfig::default();
-        let skills = discover_local_skills(temp_dir.path(), &config).unwrap();
+        let skills = discover_local_skills(temp_dir.path(), &config, &mut Vec::new()).unwrap();
 
         assert_eq!(skills.len(), 1);
         assert_eq!(skills[0].name, "test-skill");
@@ -344,10 +737,14 @@ internal: true
         let config = DiscoveryConfig {
             allow_internal: false,
             max_depth: 3,
+            ..Default::default()
         };
 
-        let skills = discover_local_skills(temp_dir.path(), &config).unwrap();
+        let mut invalid = Vec::new();
+        let skills = discover_local_skills(temp_dir.path(), &config, &mut invalid).unwrap();
         assert_eq!(skills.len(), 0);
+        // Hidden, not broken
+        assert!(invalid.is_empty());
     }
 
     #[test]
@@ -369,9 +766,10 @@ internal: true
         let config = DiscoveryConfig {
             allow_internal: true,
             max_depth: 3,
+            ..Default::default()
         };
 
-        let skills = discover_local_skills(temp_dir.path(), &config).unwrap();
+        let skills = discover_local_skills(temp_dir.path(), &config, &mut Vec::new()).unwrap();
         assert_eq!(skills.len(), 1);
         assert_eq!(skills[0].name, "internal-skill");
         assert!(skills[0].metadata.internal);
@@ -390,10 +788,30 @@ description: Missing name
 "#;
         fs::write(&skill_path, content).unwrap();
 
-        let config = DiscoveryConfig::default();
-        let result = parse_skill_file(&skill_path, &config);
+        let result = parse_skill_file(&skill_path);
         assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Missing 'name'"));
+        let err = result.unwrap_err();
+        assert!(err.to_string().contains("Missing 'name'"));
+        assert_eq!(err.code(), "invalid_skill");
+        assert_eq!(err.path(), Some(skill_path.as_path()));
+    }
+
+    #[test]
+    fn test_parse_skill_file_invalid_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let skill_path = temp_dir.path().join("SKILL.md");
+
+        let content = r#"---
+name: ../../.ssh
+description: Escapes the install directory
+---
+
+# Skill
+"#;
+        fs::write(&skill_path, content).unwrap();
+
+        let err = parse_skill_file(&skill_path).unwrap_err();
+        assert!(matches!(err, SkillsError::InvalidSkillName(_)));
     }
 
     #[test]
@@ -409,8 +827,7 @@ name: test-skill
 "#;
         fs::write(&skill_path, content).unwrap();
 
-        let config = DiscoveryConfig::default();
-        let result = parse_skill_file(&skill_path, &config);
+        let result = parse_skill_file(&skill_path);
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
@@ -439,18 +856,21 @@ metadata:
         let config = DiscoveryConfig {
             allow_internal: false,
             max_depth: 3,
+            ..Default::default()
         };
 
         // Should filter out when internal is nested under metadata
-        let skills = discover_local_skills(temp_dir.path(), &config).unwrap();
+        let skills = discover_local_skills(temp_dir.path(), &config, &mut Vec::new()).unwrap();
         assert_eq!(skills.len(), 0);
 
         // Should allow when config permits
         let config_allow = DiscoveryConfig {
             allow_internal: true,
             max_depth: 3,
+            ..Default::default()
         };
-        let skills_allowed = discover_local_skills(temp_dir.path(), &config_allow).unwrap();
+        let skills_allowed =
+            discover_local_skills(temp_dir.path(), &config_allow, &mut Vec::new()).unwrap();
         assert_eq!(skills_allowed.len(), 1);
         assert!(skills_allowed[0].metadata.internal);
     }
@@ -530,6 +950,7 @@ description: A skill from GitHub
         let config = DiscoveryConfig {
             allow_internal: false,
             max_depth: 3,
+            ..Default::default()
         };
         let skills = discover_skills_with_provider(&source, &config, Some(&provider)).unwrap();
 
@@ -554,4 +975,447 @@ description: A skill from GitHub
         // Without provider, should return empty
         assert_eq!(skills.len(), 0);
     }
+
+    #[test]
+    fn test_discover_applies_skill_filter() {
+        use crate::providers::MockProvider;
+        use crate::types::{SkillFilter, SkillMetadata};
+
+        let skill = |name: &str| Skill {
+            name: name.to_string(),
+            description: format!("{} skill", name),
+            path: None,
+            raw_content: "test".to_string(),
+            metadata: SkillMetadata::default(),
+            auxiliary_files: Default::default(),
+        };
+        let mut bad_name = skill("bad");
+        bad_name.name = "Not Valid".to_string();
+
+        let provider = MockProvider::new(vec![
+            skill("pdf-tools"),
+            skill("pdf-forms"),
+            skill("pdf-old"),
+            skill("docx"),
+            skill("xlsx"),
+            bad_name,
+        ]);
+        let source = Source {
+            source_type: SourceType::Github,
+            url: Some("https://github.com/example/repo".to_string()),
+            subpath: None,
+            skill_filter: Some(SkillFilter::new(&["pdf-*", "docx"], &["pdf-old"])),
+            ref_: None,
+        };
+
+        // Unselected skills are dropped before validation, so the invalid name is ignored
+        let config = DiscoveryConfig::default();
+        let skills = discover_skills_with_provider(&source, &config, Some(&provider)).unwrap();
+        let names: Vec<_> = skills.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["docx", "pdf-forms", "pdf-tools"]);
+    }
+
+    #[test]
+    fn test_provider_receives_skill_filter() {
+        use crate::types::SkillFilter;
+        use std::sync::Mutex;
+
+        /// Provider that records the filter and returns only what it selects
+        struct FilteringProvider(Mutex<Option<SkillFilter>>);
+        impl SkillProvider for FilteringProvider {
+            fn discover_skills(&self, _: &str, _: Option<&str>) -> anyhow::Result<Vec<Skill>> {
+                anyhow::bail!("discovery must go through discover_skills_matching")
+            }
+            fn discover_skills_at(
+                &self,
+                _: &str,
+                _: Option<&str>,
+                _: Option<&str>,
+            ) -> anyhow::Result<Vec<Skill>> {
+                anyhow::bail!("discovery must go through discover_skills_matching")
+            }
+            fn discover_skills_matching(
+                &self,
+                _: &str,
+                _: Option<&str>,
+                _: Option<&str>,
+                filter: Option<&SkillFilter>,
+            ) -> anyhow::Result<Vec<Skill>> {
+                *self.0.lock().unwrap() = filter.cloned();
+                Ok(Vec::new())
+            }
+            fn fetch_skill(&self, _: &Skill, _: &Path) -> anyhow::Result<()> {
+                unreachable!()
+            }
+            fn get_folder_hash(&self, _: &Skill) -> anyhow::Result<String> {
+                unreachable!()
+            }
+        }
+
+        let filter = SkillFilter::new(&["pdf-*"], &["pdf-old"]);
+        let source = Source {
+            source_type: SourceType::Github,
+            url: Some("https://github.com/example/repo".to_string()),
+            subpath: None,
+            skill_filter: Some(filter.clone()),
+            ref_: None,
+        };
+        let provider = FilteringProvider(Mutex::new(None));
+        discover_skills_with_provider(&source, &DiscoveryConfig::default(), Some(&provider))
+            .unwrap();
+        assert_eq!(*provider.0.lock().unwrap(), Some(filter));
+    }
+
+    #[test]
+    fn test_discover_local_skills_with_filter() {
+        use crate::types::SkillFilter;
+
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["alpha", "beta"] {
+            let dir = temp_dir.path().join("skills").join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::write(
+                dir.join("SKILL.md"),
+                format!("---\nname: {}\ndescription: {}\n---\n", name, name),
+            )
+            .unwrap();
+        }
+
+        let source = Source {
+            source_type: SourceType::Local,
+            url: Some(temp_dir.path().display().to_string()),
+            subpath: None,
+            skill_filter: Some(SkillFilter::new(&[] as &[&str], &["a*"])),
+            ref_: None,
+        };
+        let skills = discover_skills(&source, &DiscoveryConfig::default()).unwrap();
+        assert_eq!(skills.len(), 1);
+        assert_eq!(skills[0].name, "beta");
+    }
+
+    fn write_skill(dir: &Path, name: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("SKILL.md"),
+            format!("---\nname: {}\ndescription: {}\n---\n", name, name),
+        )
+        .unwrap();
+    }
+
+    fn skill_names(base: &Path, config: &DiscoveryConfig) -> Vec<String> {
+        let mut names: Vec<_> = discover_local_skills(base, config, &mut Vec::new())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn test_discover_curated_root_without_duplicates() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join("skills/.curated/docx"), "docx");
+
+        let names = skill_names(temp_dir.path(), &DiscoveryConfig::default());
+        assert_eq!(names, vec!["docx", "pdf"]);
+    }
+
+    #[test]
+    fn test_discover_custom_search_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join("my-skills/docx"), "docx");
+
+        let config = DiscoveryConfig {
+            search_roots: vec![PathBuf::from("my-skills")],
+            ..Default::default()
+        };
+        assert_eq!(skill_names(temp_dir.path(), &config), vec!["docx"]);
+    }
+
+    #[test]
+    fn test_recursive_search_skips_excluded_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(
+            &temp_dir.path().join("node_modules/pkg"),
+            "from-node-modules",
+        );
+        write_skill(&temp_dir.path().join("target/pkg"), "from-target");
+        write_skill(&temp_dir.path().join(".git/pkg"), "from-git");
+        write_skill(&temp_dir.path().join("docs/pdf"), "pdf");
+
+        let names = skill_names(temp_dir.path(), &DiscoveryConfig::default());
+        assert_eq!(names, vec!["pdf"]);
+    }
+
+    #[test]
+    fn test_recursive_search_honours_ignore_files() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("build/pdf"), "from-build");
+        write_skill(&temp_dir.path().join("drafts/docx"), "from-drafts");
+        write_skill(&temp_dir.path().join("docs/xlsx"), "xlsx");
+        fs::write(temp_dir.path().join(".gitignore"), "build/\n").unwrap();
+        fs::write(temp_dir.path().join(SKILLS_IGNORE_FILE), "drafts\n").unwrap();
+
+        let names = skill_names(temp_dir.path(), &DiscoveryConfig::default());
+        assert_eq!(names, vec!["xlsx"]);
+
+        let config = DiscoveryConfig {
+            use_ignore_files: false,
+            ..Default::default()
+        };
+        assert_eq!(
+            skill_names(temp_dir.path(), &config),
+            vec!["from-build", "from-drafts", "xlsx"]
+        );
+    }
+
+    #[test]
+    fn test_search_root_honours_ignore_files_above_it() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join("skills/generated/docx"), "generated");
+        write_skill(&temp_dir.path().join("skills/drafts/xlsx"), "draft");
+        fs::write(
+            temp_dir.path().join(".gitignore"),
+            "skills/generated/
+",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join(SKILLS_IGNORE_FILE),
+            "drafts
+",
+        )
+        .unwrap();
+
+        let names = skill_names(temp_dir.path(), &DiscoveryConfig::default());
+        assert_eq!(names, vec!["pdf"]);
+    }
+
+    #[test]
+    fn test_invalid_skills_are_reported() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join("skills/bad"), "Bad Name");
+        fs::create_dir_all(temp_dir.path().join("skills/broken")).unwrap();
+        fs::write(
+            temp_dir.path().join("skills/broken/SKILL.md"),
+            "no frontmatter",
+        )
+        .unwrap();
+
+        let source = Source {
+            source_type: crate::types::SourceType::Local,
+            url: Some(temp_dir.path().display().to_string()),
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+        let report =
+            discover_skills_with_report(&source, &DiscoveryConfig::default(), None).unwrap();
+        assert_eq!(report.skills.len(), 1);
+
+        let mut invalid = report.invalid;
+        invalid.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(invalid.len(), 2);
+        assert!(invalid[0].path.ends_with("bad/SKILL.md"));
+        assert!(
+            invalid[0].reason.contains("Invalid skill name"),
+            "{}",
+            invalid[0].reason
+        );
+        assert!(invalid[1].path.ends_with("broken/SKILL.md"));
+        assert!(
+            invalid[1].reason.contains("frontmatter"),
+            "{}",
+            invalid[1].reason
+        );
+    }
+
+    #[test]
+    fn test_max_depth_is_relative_to_each_root() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills"), "at-root");
+        write_skill(&temp_dir.path().join("skills/one"), "one-level");
+        write_skill(&temp_dir.path().join("skills/a/two"), "two-levels");
+        write_skill(&temp_dir.path().join(".claude/skills/other"), "other-root");
+
+        let config = |max_depth| DiscoveryConfig {
+            max_depth,
+            ..Default::default()
+        };
+        assert_eq!(skill_names(temp_dir.path(), &config(0)), vec!["at-root"]);
+        assert_eq!(
+            skill_names(temp_dir.path(), &config(1)),
+            vec!["at-root", "one-level", "other-root"]
+        );
+        assert_eq!(
+            skill_names(temp_dir.path(), &config(2)),
+            vec!["at-root", "one-level", "other-root", "two-levels"]
+        );
+        // Unlimited depth doesn't overflow
+        assert_eq!(
+            skill_names(temp_dir.path(), &config(usize::MAX)),
+            vec!["at-root", "one-level", "other-root", "two-levels"]
+        );
+    }
+
+    #[test]
+    fn test_recursive_fallback_with_zero_depth() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(temp_dir.path(), "top");
+        write_skill(&temp_dir.path().join("nested"), "nested");
+
+        let config = DiscoveryConfig {
+            max_depth: 0,
+            ..Default::default()
+        };
+        assert_eq!(skill_names(temp_dir.path(), &config), vec!["top"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_symlinked_skill_reported_once() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join(".agents/skills/pdf"), "pdf");
+        fs::create_dir_all(temp_dir.path().join(".claude/skills")).unwrap();
+        std::os::unix::fs::symlink(
+            "../../.agents/skills/pdf",
+            temp_dir.path().join(".claude/skills/pdf"),
+        )
+        .unwrap();
+
+        let skills = discover_local_skills(
+            temp_dir.path(),
+            &DiscoveryConfig::default(),
+            &mut Vec::new(),
+        )
+        .unwrap();
+        assert_eq!(skills.len(), 1);
+        assert!(skills[0].path.as_ref().unwrap().contains(".agents"));
+    }
+
+    #[test]
+    fn test_discover_output_is_sorted() {
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["zeta", "alpha", "mid"] {
+            write_skill(&temp_dir.path().join("skills").join(name), name);
+        }
+        let source = Source {
+            source_type: SourceType::Local,
+            url: Some(temp_dir.path().display().to_string()),
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+        let names: Vec<_> = discover_skills(&source, &DiscoveryConfig::default())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.name)
+            .collect();
+        assert_eq!(names, vec!["alpha", "mid", "zeta"]);
+    }
+
+    fn local_source(base: &Path) -> Source {
+        Source {
+            source_type: SourceType::Local,
+            url: Some(base.display().to_string()),
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        }
+    }
+
+    #[test]
+    fn test_duplicate_names_first_wins_by_priority() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join(".claude/skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+
+        let report = discover_skills_with_report(
+            &local_source(temp_dir.path()),
+            &DiscoveryConfig::default(),
+            None,
+        )
+        .unwrap();
+
+        assert_eq!(report.skills.len(), 1);
+        assert!(report.skills[0]
+            .path
+            .as_ref()
+            .unwrap()
+            .ends_with("skills/pdf/SKILL.md"));
+        assert!(!report.skills[0].path.as_ref().unwrap().contains(".claude"));
+
+        assert_eq!(report.duplicates.len(), 1);
+        let duplicate = &report.duplicates[0];
+        assert_eq!(duplicate.name, "pdf");
+        assert_eq!(duplicate.kept, report.skills[0].path);
+        assert!(duplicate.duplicate.as_ref().unwrap().contains(".claude"));
+        assert_eq!(duplicate.renamed_to, None);
+    }
+
+    #[test]
+    fn test_duplicate_names_error_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join(".claude/skills/pdf"), "pdf");
+
+        let config = DiscoveryConfig {
+            on_duplicate: DuplicatePolicy::Error,
+            ..Default::default()
+        };
+        let err = discover_skills(&local_source(temp_dir.path()), &config).unwrap_err();
+        assert_eq!(err.code(), "duplicate_skill");
+        let message = err.to_string();
+        assert!(message.contains("skills/pdf/SKILL.md"), "{}", message);
+        assert!(
+            message.contains(".claude/skills/pdf/SKILL.md"),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn test_duplicate_names_rename_policy() {
+        let temp_dir = TempDir::new().unwrap();
+        write_skill(&temp_dir.path().join("skills/pdf"), "pdf");
+        write_skill(&temp_dir.path().join(".claude/skills/pdf"), "pdf");
+
+        let config = DiscoveryConfig {
+            on_duplicate: DuplicatePolicy::Rename,
+            ..Default::default()
+        };
+        let report =
+            discover_skills_with_report(&local_source(temp_dir.path()), &config, None).unwrap();
+
+        let names: Vec<_> = report.skills.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, vec!["claude-pdf", "pdf"]);
+        assert!(report.skills[0].raw_content.contains("name: claude-pdf\n"));
+        assert_eq!(
+            report.duplicates[0].renamed_to.as_deref(),
+            Some("claude-pdf")
+        );
+    }
+
+    #[test]
+    fn test_namespaced_name_falls_back_to_number() {
+        let skill = Skill {
+            name: "pdf".to_string(),
+            description: "pdf".to_string(),
+            path: None,
+            raw_content: "---\nname: pdf\ndescription: pdf\n---\n".to_string(),
+            metadata: SkillMetadata::default(),
+            auxiliary_files: Default::default(),
+        };
+        let taken: HashSet<String> = ["pdf".to_string(), "pdf-2".to_string()].into();
+        assert_eq!(namespaced_name(&skill, &taken), "pdf-3");
+        assert_eq!(
+            rename_in_frontmatter(&skill.raw_content, "pdf-3"),
+            "---\nname: pdf-3\ndescription: pdf\n---\n"
+        );
+    }
 }
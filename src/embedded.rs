@@ -1,5 +1,5 @@
-use crate::types::{Skill, SkillMetadata};
-use anyhow::{Context, Result};
+use crate::error::{Result, SkillsError};
+use crate::types::{validate_skill_name, Skill, SkillMetadata};
 use std::collections::HashMap;
 
 const AGENT_SKILLS_RUST_CONTENT: &str = include_str!("../skills/agent-skills-rs/SKILL.md");
@@ -10,7 +10,9 @@ fn parse_frontmatter(content: &str) -> Result<(SkillMetadata, String, String)> {
 
     // Check for frontmatter
     if lines.is_empty() || !lines[0].trim().starts_with("---") {
-        anyhow::bail!("Missing frontmatter in skill definition");
+        return Err(SkillsError::invalid_skill(
+            "Missing frontmatter in skill definition",
+        ));
     }
 
     // Find the closing ---
@@ -18,7 +20,7 @@ fn parse_frontmatter(content: &str) -> Result<(SkillMetadata, String, String)> {
         .iter()
         .skip(1)
         .position(|line| line.trim() == "---")
-        .context("Frontmatter not properly closed")?;
+        .ok_or_else(|| SkillsError::invalid_skill("Frontmatter not properly closed"))?;
 
     let frontmatter_lines = &lines[1..end_idx + 1];
     let mut name = None;
@@ -36,8 +38,10 @@ fn parse_frontmatter(content: &str) -> Result<(SkillMetadata, String, String)> {
         }
     }
 
-    let name = name.context("Missing 'name' in frontmatter")?;
-    let description = description.context("Missing 'description' in frontmatter")?;
+    let name = name.ok_or_else(|| SkillsError::invalid_skill("Missing 'name' in frontmatter"))?;
+    validate_skill_name(&name)?;
+    let description = description
+        .ok_or_else(|| SkillsError::invalid_skill("Missing 'description' in frontmatter"))?;
 
     Ok((SkillMetadata::default(), name, description))
 }
@@ -195,6 +199,13 @@ description: A simple skill
         assert!(skill.auxiliary_files.is_empty());
     }
 
+    #[test]
+    fn test_register_embedded_skill_rejects_invalid_name() {
+        let skill_md = "---\nname: /tmp/x\ndescription: Absolute name\n---\n";
+        let err = register_embedded_skill(skill_md, &[]).unwrap_err();
+        assert!(matches!(err, SkillsError::InvalidSkillName(_)));
+    }
+
     #[test]
     fn test_parse_frontmatter_not_closed() {
         let content = r#"---
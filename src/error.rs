@@ -0,0 +1,236 @@
+use crate::types::InvalidSkillName;
+use std::path::PathBuf;
+
+/// Result type used throughout the library
+pub type Result<T, E = SkillsError> = std::result::Result<T, E>;
+
+/// Errors returned by the library.
+///
+/// Each variant has a stable machine-readable code (see [`SkillsError::code`])
+/// so callers can branch on the error class without matching on messages.
+#[derive(Debug, thiserror::Error)]
+pub enum SkillsError {
+    /// A filesystem operation failed
+    #[error("Failed to {action}: {path:?}")]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+
+    /// The skill name doesn't follow the naming rules
+    #[error(transparent)]
+    InvalidSkillName(#[from] InvalidSkillName),
+
+    /// A SKILL.md (or embedded skill definition) couldn't be parsed
+    #[error("Invalid skill definition: {reason}")]
+    InvalidSkill {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+
+    /// A requested skill doesn't exist in the source
+    #[error("Skill not found: {name}")]
+    SkillNotFound { name: String },
+
+    /// Two skills in a source share a name
+    #[error(
+        "Duplicate skill name {name:?}: {} and {}",
+        display_path(first),
+        display_path(second)
+    )]
+    DuplicateSkill {
+        name: String,
+        first: Option<PathBuf>,
+        second: Option<PathBuf>,
+    },
+
+    /// A path would be written outside the skill directory
+    #[error("{reason}: {path:?}")]
+    UnsafePath { path: PathBuf, reason: String },
+
+    /// The source specification is incomplete or inconsistent
+    #[error("Invalid source: {reason}")]
+    InvalidSource { reason: String },
+
+    /// A skill provider failed to discover or fetch skills
+    #[error("{context}: {error:#}")]
+    Provider {
+        context: String,
+        error: anyhow::Error,
+    },
+
+    /// The skill couldn't be placed in a target directory with any install mode
+    #[error("Failed to install skill to {path:?}: {reason}")]
+    InstallFailed { path: PathBuf, reason: String },
+
+    /// The lock file exists but can't be parsed
+    #[error("Failed to parse lock file: {path:?}")]
+    LockCorrupt {
+        path: PathBuf,
+        #[source]
+        source: serde_json::Error,
+    },
+
+    /// The lock file is valid JSON but doesn't follow the lock format
+    #[error("Invalid lock file {path:?}: {field}: {reason}")]
+    LockInvalid {
+        path: PathBuf,
+        /// JSON path of the offending value
+        field: String,
+        reason: String,
+    },
+
+    /// The skills manifest can't be parsed or is inconsistent
+    #[error("Invalid skills manifest: {reason}")]
+    InvalidManifest {
+        path: Option<PathBuf>,
+        reason: String,
+    },
+
+    /// A frozen install found a skill that differs from what the lock records
+    #[error("Lock mismatch for skill {name}: {field} is {actual}, lock records {expected}")]
+    LockMismatch {
+        name: String,
+        field: &'static str,
+        expected: String,
+        actual: String,
+    },
+
+    /// An introspection request named a command that doesn't exist
+    #[error("Command not found: {name}")]
+    CommandNotFound { name: String },
+
+    /// The command exists but doesn't publish the requested kind of schema
+    #[error("No {kind} schema for command: {name}")]
+    SchemaNotFound { name: String, kind: &'static str },
+
+    /// Serializing output (lock file, introspection JSON) failed
+    #[error("Failed to serialize {what}")]
+    Serialize {
+        what: &'static str,
+        #[source]
+        source: serde_json::Error,
+    },
+}
+
+impl SkillsError {
+    /// Stable machine-readable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            SkillsError::Io { .. } => "io_error",
+            SkillsError::InvalidSkillName(_) => "invalid_skill_name",
+            SkillsError::InvalidSkill { .. } => "invalid_skill",
+            SkillsError::SkillNotFound { .. } => "skill_not_found",
+            SkillsError::DuplicateSkill { .. } => "duplicate_skill",
+            SkillsError::UnsafePath { .. } => "unsafe_path",
+            SkillsError::InvalidSource { .. } => "invalid_source",
+            SkillsError::Provider { .. } => "provider_error",
+            SkillsError::InstallFailed { .. } => "install_failed",
+            SkillsError::LockCorrupt { .. } => "lock_corrupt",
+            SkillsError::LockInvalid { .. } => "lock_invalid",
+            SkillsError::LockMismatch { .. } => "lock_mismatch",
+            SkillsError::InvalidManifest { .. } => "invalid_manifest",
+            SkillsError::CommandNotFound { .. } => "command_not_found",
+            SkillsError::SchemaNotFound { .. } => "schema_not_found",
+            SkillsError::Serialize { .. } => "serialize_error",
+        }
+    }
+
+    /// Path the error refers to, if any
+    pub fn path(&self) -> Option<&std::path::Path> {
+        match self {
+            SkillsError::Io { path, .. }
+            | SkillsError::UnsafePath { path, .. }
+            | SkillsError::InstallFailed { path, .. }
+            | SkillsError::LockCorrupt { path, .. }
+            | SkillsError::LockInvalid { path, .. } => Some(path),
+            SkillsError::InvalidSkill { path, .. } | SkillsError::InvalidManifest { path, .. } => {
+                path.as_deref()
+            }
+            SkillsError::DuplicateSkill { second, .. } => second.as_deref(),
+            _ => None,
+        }
+    }
+
+    /// Build a closure that wraps an I/O error with the action and path it relates to
+    pub(crate) fn io(
+        action: &'static str,
+        path: impl Into<PathBuf>,
+    ) -> impl FnOnce(std::io::Error) -> SkillsError {
+        let path = path.into();
+        move |source| SkillsError::Io {
+            action,
+            path,
+            source,
+        }
+    }
+
+    pub(crate) fn invalid_skill(reason: impl Into<String>) -> SkillsError {
+        SkillsError::InvalidSkill {
+            path: None,
+            reason: reason.into(),
+        }
+    }
+
+    pub(crate) fn unsafe_path(path: impl Into<PathBuf>, reason: impl Into<String>) -> SkillsError {
+        SkillsError::UnsafePath {
+            path: path.into(),
+            reason: reason.into(),
+        }
+    }
+}
+
+fn display_path(path: &Option<PathBuf>) -> String {
+    path.as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "<unknown path>".to_string())
+}
+
+/// Format an error together with all of its sources, separated by `": "`
+pub(crate) fn error_chain(error: &dyn std::error::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = error.source();
+    while let Some(cause) = source {
+        message.push_str(": ");
+        message.push_str(&cause.to_string());
+        source = cause.source();
+    }
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_codes_are_stable() {
+        let io = SkillsError::io("read lock file", "/tmp/lock.json")(std::io::Error::from(
+            std::io::ErrorKind::NotFound,
+        ));
+        assert_eq!(io.code(), "io_error");
+        assert_eq!(io.path(), Some(std::path::Path::new("/tmp/lock.json")));
+
+        let name = SkillsError::from(crate::types::validate_skill_name("../x").unwrap_err());
+        assert_eq!(name.code(), "invalid_skill_name");
+
+        let not_found = SkillsError::CommandNotFound {
+            name: "nope".to_string(),
+        };
+        assert_eq!(not_found.code(), "command_not_found");
+        assert_eq!(not_found.to_string(), "Command not found: nope");
+    }
+
+    #[test]
+    fn test_error_chain_includes_sources() {
+        let error = SkillsError::io("read lock file", "/tmp/lock.json")(std::io::Error::new(
+            std::io::ErrorKind::PermissionDenied,
+            "permission denied",
+        ));
+        assert_eq!(
+            error_chain(&error),
+            "Failed to read lock file: \"/tmp/lock.json\": permission denied"
+        );
+    }
+}
@@ -1,7 +1,10 @@
+use crate::discovery::rename_in_frontmatter;
+use crate::error::{error_chain, Result, SkillsError};
 use crate::providers::SkillProvider;
-use crate::types::Skill;
-use anyhow::{bail, Context, Result};
+use crate::types::{validate_skill_name, Skill};
+use serde::{Deserialize, Serialize};
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
 
 /// Result of skill installation
@@ -9,22 +12,66 @@ use std::path::{Path, PathBuf};
 pub struct InstallResult {
     pub path: PathBuf,
     pub symlink_failed: bool,
+    /// Where the skill was placed in each target directory and how
+    pub targets: Vec<TargetInstall>,
+}
+
+/// Outcome of installing a skill into a single target directory
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetInstall {
+    pub path: PathBuf,
+    /// The mode configured in `InstallConfig::mode`
+    pub requested_mode: InstallMode,
+    /// The mode that actually succeeded (may differ from the requested mode)
+    pub effective_mode: InstallMode,
+    /// Why earlier modes in the chain failed, if a fallback was used
+    pub fallback_error: Option<String>,
 }
 
 /// Installation mode
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "lowercase")]
 pub enum InstallMode {
+    /// Symlink the skill directory to the canonical location
     Symlink,
+    /// Hard link every file from the canonical location
+    Hardlink,
+    /// Copy-on-write clone of every file (Linux `FICLONE`)
+    Reflink,
+    /// Plain recursive copy
     Copy,
 }
 
+impl InstallMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            InstallMode::Symlink => "symlink",
+            InstallMode::Hardlink => "hardlink",
+            InstallMode::Reflink => "reflink",
+            InstallMode::Copy => "copy",
+        }
+    }
+}
+
+/// How symlinks from target directories to the canonical location are written
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkStyle {
+    /// Link with the absolute canonical path
+    Absolute,
+    /// Link with a path relative to the target directory, so links survive
+    /// moving the project checkout and can be committed to version control
+    Relative,
+}
+
 /// Installation configuration
 #[derive(Debug, Clone)]
 pub struct InstallConfig {
     pub mode: InstallMode,
     pub canonical_dir: PathBuf,
     pub target_dirs: Vec<PathBuf>,
-    pub fallback_to_copy: bool,
+    /// Modes tried in order when `mode` fails for a target directory
+    pub fallback: Vec<InstallMode>,
+    pub link_style: LinkStyle,
 }
 
 impl InstallConfig {
@@ -33,7 +80,16 @@ impl InstallConfig {
             mode: InstallMode::Symlink,
             canonical_dir,
             target_dirs: Vec::new(),
-            fallback_to_copy: true,
+            fallback: vec![InstallMode::Copy],
+            link_style: LinkStyle::Absolute,
+        }
+    }
+
+    /// Configuration for project-scope installs, which use relative links
+    pub fn for_project(canonical_dir: PathBuf) -> Self {
+        Self {
+            link_style: LinkStyle::Relative,
+            ..Self::new(canonical_dir)
         }
     }
 }
@@ -49,162 +105,501 @@ pub fn install_skill_with_provider(
     config: &InstallConfig,
     provider: Option<&dyn SkillProvider>,
 ) -> Result<InstallResult> {
+    // The name becomes a directory under canonical and target dirs; never
+    // trust it without validation, even if the skill was built by hand
+    validate_skill_name(&skill.name)?;
+
     // Create canonical path
     let canonical_path = config.canonical_dir.join(&skill.name);
+    refuse_symlinked_install_dirs(&config.canonical_dir, &canonical_path)?;
 
     // Ensure parent directory exists
     if let Some(parent) = canonical_path.parent() {
         fs::create_dir_all(parent)
-            .with_context(|| format!("Failed to create canonical directory: {:?}", parent))?;
+            .map_err(SkillsError::io("create canonical directory", parent))?;
     }
 
+    // Start from an empty directory, so files left by a previous install neither linger
+    // nor make the installed skill differ from the hash recorded for it
+    remove_existing(&canonical_path)?;
+
     // Fetch content to canonical location
     if let Some(provider) = provider {
         // Use provider to fetch skill content
         provider
             .fetch_skill(skill, &canonical_path)
-            .with_context(|| format!("Failed to fetch skill via provider: {:?}", skill.name))?;
+            .map_err(|error| SkillsError::Provider {
+                context: format!("Failed to fetch skill via provider: {:?}", skill.name),
+                error,
+            })?;
+
+        // A duplicate renamed during discovery is fetched under its name in the source
+        let skill_file_path = contained_write_path(&canonical_path, Path::new("SKILL.md"))?;
+        if let Ok(fetched) = fs::read_to_string(&skill_file_path) {
+            let renamed = rename_in_frontmatter(&fetched, &skill.name);
+            if renamed != fetched {
+                fs::write(&skill_file_path, renamed)
+                    .map_err(SkillsError::io("write skill file", &skill_file_path))?;
+            }
+        }
     } else {
         // Write skill content directly (for embedded/local skills)
         fs::create_dir_all(&canonical_path)
-            .with_context(|| format!("Failed to create skill directory: {:?}", canonical_path))?;
+            .map_err(SkillsError::io("create skill directory", &canonical_path))?;
 
-        let skill_file_path = canonical_path.join("SKILL.md");
+        let skill_file_path = contained_write_path(&canonical_path, Path::new("SKILL.md"))?;
         fs::write(&skill_file_path, &skill.raw_content)
-            .with_context(|| format!("Failed to write skill file: {:?}", skill_file_path))?;
+            .map_err(SkillsError::io("write skill file", &skill_file_path))?;
 
         // Write auxiliary files alongside SKILL.md
         for (rel_path, content) in &skill.auxiliary_files {
             // Security: reject absolute paths and path traversal outside skill root
             let rel = Path::new(rel_path);
             if rel.is_absolute() {
-                bail!("Auxiliary file path must be relative, got: {:?}", rel_path);
+                return Err(SkillsError::unsafe_path(
+                    rel,
+                    "Auxiliary file path must be relative",
+                ));
             }
-            // Normalize and ensure path stays within canonical_path
-            let file_path = canonical_path.join(rel_path);
-            let canonical_file = file_path
-                .canonicalize()
-                .unwrap_or_else(|_| file_path.clone());
             // Before the file exists we can't canonicalize it; check components instead
             for component in rel.components() {
                 use std::path::Component;
                 match component {
                     Component::ParentDir => {
-                        bail!(
-                            "Auxiliary file path must not traverse outside skill directory: {:?}",
-                            rel_path
-                        );
+                        return Err(SkillsError::unsafe_path(
+                            rel,
+                            "Auxiliary file path must not traverse outside skill directory",
+                        ));
                     }
                     Component::RootDir => {
-                        bail!(
-                            "Auxiliary file path must not be rooted (contains root separator): {:?}",
-                            rel_path
-                        );
+                        return Err(SkillsError::unsafe_path(
+                            rel,
+                            "Auxiliary file path must not be rooted (contains root separator)",
+                        ));
                     }
                     Component::Prefix(_) => {
-                        bail!(
-                            "Auxiliary file path must not contain a path prefix (e.g. drive letter): {:?}",
-                            rel_path
-                        );
+                        return Err(SkillsError::unsafe_path(
+                            rel,
+                            "Auxiliary file path must not contain a path prefix (e.g. drive letter)",
+                        ));
                     }
                     _ => {}
                 }
             }
-            let _ = canonical_file; // used for future post-write checks
-            if let Some(parent) = file_path.parent() {
-                fs::create_dir_all(parent).with_context(|| {
-                    format!(
-                        "Failed to create directory for auxiliary file: {:?}",
-                        parent
-                    )
-                })?;
-            }
+            // Existing directories inside the skill root may be symlinks; make sure
+            // the resolved location still lies within the skill root
+            let file_path = contained_write_path(&canonical_path, rel)?;
             fs::write(&file_path, content)
-                .with_context(|| format!("Failed to write auxiliary file: {:?}", file_path))?;
+                .map_err(SkillsError::io("write auxiliary file", &file_path))?;
         }
     }
 
-    // Track if any symlink failed
-    let mut any_symlink_failed = false;
-
     // Link or copy to target directories
+    let mut targets = Vec::new();
     for target_dir in &config.target_dirs {
-        let symlink_failed = link_or_copy_skill(&canonical_path, target_dir, &skill.name, config)?;
-        any_symlink_failed = any_symlink_failed || symlink_failed;
+        targets.push(link_or_copy_skill(
+            &canonical_path,
+            target_dir,
+            &skill.name,
+            config,
+        )?);
     }
 
+    let symlink_failed = config.mode == InstallMode::Symlink
+        && targets.iter().any(|t| t.effective_mode != t.requested_mode);
+
     Ok(InstallResult {
         path: canonical_path,
-        symlink_failed: any_symlink_failed,
+        symlink_failed,
+        targets,
     })
 }
 
-/// Link or copy skill from canonical location to target directory
-/// Returns true if symlink failed and fallback to copy was used
+/// Refuse to install when the install directory or the skill's directory in it is a
+/// symlink, since providers and writes would follow it out of the install tree
+fn refuse_symlinked_install_dirs(canonical_dir: &Path, canonical_path: &Path) -> Result<()> {
+    for path in [canonical_dir, canonical_path] {
+        let is_symlink =
+            fs::symlink_metadata(path).is_ok_and(|metadata| metadata.file_type().is_symlink());
+        if is_symlink {
+            return Err(SkillsError::unsafe_path(
+                path,
+                "Refusing to install into a directory that is a symlink",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Resolve the location a file at `rel` inside `root` will be written to,
+/// creating missing parent directories along the way.
+///
+/// Each existing component is inspected: symlinks are followed only if they
+/// resolve to a location inside `root`, and a dangling symlink as the final
+/// component is refused because writing to it would create its target.
+/// `root` itself must not be a symlink, or every check would pass trivially.
+fn contained_write_path(root: &Path, rel: &Path) -> Result<PathBuf> {
+    use std::path::Component;
+
+    let is_symlink = fs::symlink_metadata(root)
+        .map_err(SkillsError::io("inspect skill directory", root))?
+        .file_type()
+        .is_symlink();
+    if is_symlink {
+        return Err(SkillsError::unsafe_path(
+            root,
+            "Refusing to write into a skill directory that is a symlink",
+        ));
+    }
+
+    let root = root
+        .canonicalize()
+        .map_err(SkillsError::io("resolve skill directory", root))?;
+
+    let components: Vec<_> = rel
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .collect();
+
+    let mut current = root.clone();
+    for (i, component) in components.iter().enumerate() {
+        let is_last = i + 1 == components.len();
+        let next = current.join(component);
+
+        match fs::symlink_metadata(&next) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let resolved = next.canonicalize().map_err(|_| {
+                    SkillsError::unsafe_path(
+                        &next,
+                        "Refusing to write through dangling symlink in skill directory",
+                    )
+                })?;
+                if !resolved.starts_with(&root) {
+                    return Err(SkillsError::unsafe_path(
+                        &next,
+                        format!(
+                            "Refusing to write through symlink that leaves the skill directory (resolves to {:?})",
+                            resolved
+                        ),
+                    ));
+                }
+                current = resolved;
+            }
+            Ok(_) => current = next,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                if !is_last {
+                    fs::create_dir(&next).map_err(SkillsError::io(
+                        "create directory for auxiliary file",
+                        &next,
+                    ))?;
+                }
+                current = next;
+            }
+            Err(e) => {
+                return Err(SkillsError::io("inspect path", next)(e));
+            }
+        }
+    }
+
+    Ok(current)
+}
+
+/// Link or copy skill from canonical location to target directory.
+/// Tries `config.mode` first, then each mode in `config.fallback`, and reports
+/// the mode that succeeded along with the errors of the modes that didn't.
 fn link_or_copy_skill(
     canonical_path: &Path,
     target_dir: &Path,
     skill_name: &str,
     config: &InstallConfig,
-) -> Result<bool> {
+) -> Result<TargetInstall> {
     fs::create_dir_all(target_dir)
-        .with_context(|| format!("Failed to create target directory: {:?}", target_dir))?;
+        .map_err(SkillsError::io("create target directory", target_dir))?;
 
     let target_path = target_dir.join(skill_name);
 
-    // Remove existing if present
-    if target_path.exists() {
-        if target_path.is_dir() {
-            fs::remove_dir_all(&target_path)?;
-        } else {
-            fs::remove_file(&target_path)?;
+    let mut failures = Vec::new();
+    for mode in std::iter::once(config.mode).chain(config.fallback.iter().copied()) {
+        // Remove existing (or partially written) target before each attempt
+        remove_existing(&target_path)?;
+
+        match place_skill(canonical_path, target_dir, &target_path, mode, config) {
+            Ok(()) => {
+                return Ok(TargetInstall {
+                    path: target_path,
+                    requested_mode: config.mode,
+                    effective_mode: mode,
+                    fallback_error: (!failures.is_empty()).then(|| failures.join("; ")),
+                });
+            }
+            // Another mode would meet the same symlink
+            Err(e @ SkillsError::UnsafePath { .. }) => {
+                remove_existing(&target_path)?;
+                return Err(e);
+            }
+            Err(e) => failures.push(format!("{}: {}", mode.as_str(), error_chain(&e))),
         }
     }
 
-    match config.mode {
+    remove_existing(&target_path)?;
+    Err(SkillsError::InstallFailed {
+        path: target_path,
+        reason: failures.join("; "),
+    })
+}
+
+/// Place the skill at `target_path` using a single install mode
+fn place_skill(
+    canonical_path: &Path,
+    target_dir: &Path,
+    target_path: &Path,
+    mode: InstallMode,
+    config: &InstallConfig,
+) -> Result<()> {
+    match mode {
         InstallMode::Symlink => {
-            // Try to create symlink
+            let link_target = match config.link_style {
+                LinkStyle::Absolute => canonical_path.to_path_buf(),
+                LinkStyle::Relative => relative_link_target(canonical_path, target_dir)?,
+            };
+
             #[cfg(unix)]
-            let result = std::os::unix::fs::symlink(canonical_path, &target_path);
+            let result = std::os::unix::fs::symlink(&link_target, target_path);
             #[cfg(windows)]
-            let result = std::os::windows::fs::symlink_dir(canonical_path, &target_path);
-
-            match result {
-                Ok(_) => Ok(false),
-                Err(e) if config.fallback_to_copy => {
-                    // Fallback to copy
-                    copy_skill(canonical_path, &target_path)?;
-                    Ok(true)
-                }
-                Err(e) => Err(e).context("Failed to create symlink"),
-            }
+            let result = std::os::windows::fs::symlink_dir(&link_target, target_path);
+
+            result.map_err(SkillsError::io("create symlink", target_path))
         }
-        InstallMode::Copy => {
-            copy_skill(canonical_path, &target_path)?;
-            Ok(false)
+        InstallMode::Hardlink => replicate_tree(
+            canonical_path,
+            target_path,
+            "create hard links",
+            &|src, dst| fs::hard_link(src, dst),
+        ),
+        InstallMode::Reflink => {
+            replicate_tree(canonical_path, target_path, "reflink skill", &reflink_file)
         }
+        InstallMode::Copy => copy_skill(canonical_path, target_path),
     }
 }
 
+/// Remove a skill from the canonical location and every target directory.
+///
+/// Returns the paths that existed and were removed.
+pub fn uninstall_skill(name: &str, config: &InstallConfig) -> Result<Vec<PathBuf>> {
+    let mut removed = unlink_skill(name, &config.target_dirs)?;
+    removed.extend(unlink_skill(name, [&config.canonical_dir])?);
+    Ok(removed)
+}
+
+/// Remove a skill's link or copy from each of `target_dirs`, leaving the canonical
+/// location alone.
+///
+/// Returns the paths that existed and were removed.
+pub fn unlink_skill<'a>(
+    name: &str,
+    target_dirs: impl IntoIterator<Item = &'a PathBuf>,
+) -> Result<Vec<PathBuf>> {
+    validate_skill_name(name)?;
+
+    let mut removed = Vec::new();
+    for dir in target_dirs {
+        let path = dir.join(name);
+        if fs::symlink_metadata(&path).is_ok() {
+            remove_existing(&path)?;
+            removed.push(path);
+        }
+    }
+    Ok(removed)
+}
+
+/// Remove a file, directory or (possibly dangling) symlink if present
+fn remove_existing(path: &Path) -> Result<()> {
+    let Ok(metadata) = fs::symlink_metadata(path) else {
+        return Ok(());
+    };
+
+    if metadata.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        // Directory symlinks on Windows must be removed with remove_dir
+        fs::remove_file(path).or_else(|_| fs::remove_dir(path))
+    }
+    .map_err(SkillsError::io("remove existing target", path))
+}
+
+/// Compute the path of `canonical_path` relative to `target_dir`.
+/// Both paths are resolved first so that symlinked parents (e.g. `/tmp` on macOS)
+/// don't produce a link that points to the wrong place.
+fn relative_link_target(canonical_path: &Path, target_dir: &Path) -> Result<PathBuf> {
+    let to = canonical_path
+        .canonicalize()
+        .map_err(SkillsError::io("resolve canonical path", canonical_path))?;
+    let from = target_dir
+        .canonicalize()
+        .map_err(SkillsError::io("resolve target directory", target_dir))?;
+
+    let to_components: Vec<_> = to.components().collect();
+    let from_components: Vec<_> = from.components().collect();
+
+    // Paths on different roots (e.g. Windows drives) can't be linked relatively
+    if to_components.first() != from_components.first() {
+        return Ok(to);
+    }
+
+    let common = to_components
+        .iter()
+        .zip(&from_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from_components.len() {
+        relative.push("..");
+    }
+    for component in &to_components[common..] {
+        relative.push(component);
+    }
+
+    Ok(relative)
+}
+
 /// Copy skill directory
 fn copy_skill(src: &Path, dst: &Path) -> Result<()> {
-    fs::create_dir_all(dst)?;
+    replicate_tree(src, dst, "copy skill", &|src, dst| {
+        fs::copy(src, dst).map(|_| ())
+    })
+}
 
-    for entry in fs::read_dir(src)? {
-        let entry = entry?;
+/// Recreate the directory structure of `src` at `dst`, placing each file with `place_file`.
+///
+/// Symlinks are recreated rather than followed, so the copy never reaches outside `src`
+/// or loops; a symlink that is absolute, dangling or resolves outside `src` is refused.
+fn replicate_tree(
+    src: &Path,
+    dst: &Path,
+    action: &'static str,
+    place_file: &dyn Fn(&Path, &Path) -> io::Result<()>,
+) -> Result<()> {
+    let root = src
+        .canonicalize()
+        .map_err(SkillsError::io("resolve skill directory", src))?;
+    replicate_dir(&root, src, dst, 0, action, place_file)
+}
+
+/// Replicate `src`, which lies `depth` directories below `root`, at `dst`
+fn replicate_dir(
+    root: &Path,
+    src: &Path,
+    dst: &Path,
+    depth: usize,
+    action: &'static str,
+    place_file: &dyn Fn(&Path, &Path) -> io::Result<()>,
+) -> Result<()> {
+    fs::create_dir_all(dst).map_err(SkillsError::io(action, dst))?;
+
+    let entries = fs::read_dir(src).map_err(SkillsError::io("read skill directory", src))?;
+    for entry in entries {
+        let entry = entry.map_err(SkillsError::io("read skill directory", src))?;
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
+        let file_type = entry
+            .file_type()
+            .map_err(SkillsError::io("inspect path", &src_path))?;
 
-        if src_path.is_dir() {
-            copy_skill(&src_path, &dst_path)?;
+        if file_type.is_symlink() {
+            let link_target = contained_link_target(root, &src_path, depth)?;
+            #[cfg(unix)]
+            let result = std::os::unix::fs::symlink(&link_target, &dst_path);
+            #[cfg(windows)]
+            let result = if src_path.is_dir() {
+                std::os::windows::fs::symlink_dir(&link_target, &dst_path)
+            } else {
+                std::os::windows::fs::symlink_file(&link_target, &dst_path)
+            };
+            result.map_err(SkillsError::io(action, &dst_path))?;
+        } else if file_type.is_dir() {
+            replicate_dir(root, &src_path, &dst_path, depth + 1, action, place_file)?;
         } else {
-            fs::copy(&src_path, &dst_path)?;
+            place_file(&src_path, &dst_path).map_err(SkillsError::io(action, &dst_path))?;
         }
     }
 
     Ok(())
 }
 
+/// Read the target of the symlink at `path`, `depth` directories below `root`, refusing
+/// one that wouldn't still point inside the skill once `root` is replicated elsewhere.
+///
+/// The target must stay within `root` both as written and once resolved, since the
+/// copy keeps the link as written but intermediate symlinks may lead elsewhere.
+fn contained_link_target(root: &Path, path: &Path, depth: usize) -> Result<PathBuf> {
+    use std::path::Component;
+
+    let link_target = fs::read_link(path).map_err(SkillsError::io("read symlink", path))?;
+    let mut depth = depth;
+    for component in link_target.components() {
+        match component {
+            Component::Normal(_) => depth += 1,
+            Component::CurDir => {}
+            Component::ParentDir if depth > 0 => depth -= 1,
+            _ => {
+                return Err(SkillsError::unsafe_path(
+                    path,
+                    format!(
+                        "Refusing to replicate symlink that leaves the skill directory (points to {:?})",
+                        link_target
+                    ),
+                ));
+            }
+        }
+    }
+    let resolved = path.canonicalize().map_err(|_| {
+        SkillsError::unsafe_path(
+            path,
+            "Refusing to replicate dangling symlink in skill directory",
+        )
+    })?;
+    if !resolved.starts_with(root) {
+        return Err(SkillsError::unsafe_path(
+            path,
+            format!(
+                "Refusing to replicate symlink that leaves the skill directory (resolves to {:?})",
+                resolved
+            ),
+        ));
+    }
+    Ok(link_target)
+}
+
+/// Clone a file with copy-on-write semantics (btrfs, XFS, ...)
+#[cfg(target_os = "linux")]
+fn reflink_file(src: &Path, dst: &Path) -> io::Result<()> {
+    use std::os::unix::io::AsRawFd;
+
+    let src_file = fs::File::open(src)?;
+    let dst_file = fs::File::create(dst)?;
+
+    // SAFETY: both descriptors are valid for the lifetime of the call
+    let ret = unsafe { libc::ioctl(dst_file.as_raw_fd(), libc::FICLONE, src_file.as_raw_fd()) };
+    if ret == -1 {
+        let err = io::Error::last_os_error();
+        drop(dst_file);
+        let _ = fs::remove_file(dst);
+        return Err(err);
+    }
+
+    dst_file.set_permissions(src_file.metadata()?.permissions())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn reflink_file(_src: &Path, _dst: &Path) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "Reflink is only supported on Linux",
+    ))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -266,6 +661,55 @@ mod tests {
         }
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_with_relative_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join(".claude/skills");
+
+        let mut config = InstallConfig::for_project(canonical_dir.clone());
+        config.target_dirs.push(target_dir.clone());
+
+        let skill = create_test_skill();
+        install_skill(&skill, &config).unwrap();
+
+        let target_path = target_dir.join("test-skill");
+        let link_target = fs::read_link(&target_path).unwrap();
+        assert_eq!(
+            link_target,
+            PathBuf::from("../../.agents/skills/test-skill")
+        );
+        assert!(target_path.join("SKILL.md").exists());
+
+        // Moving the whole project keeps the link valid
+        let moved = temp_dir.path().parent().unwrap().join(format!(
+            "{}-moved",
+            temp_dir.path().file_name().unwrap().to_string_lossy()
+        ));
+        fs::rename(temp_dir.path(), &moved).unwrap();
+        assert!(moved.join(".claude/skills/test-skill/SKILL.md").exists());
+        fs::rename(&moved, temp_dir.path()).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_with_absolute_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join(".claude/skills");
+
+        let mut config = InstallConfig::new(canonical_dir.clone());
+        config.target_dirs.push(target_dir.clone());
+        config.link_style = LinkStyle::Absolute;
+
+        let skill = create_test_skill();
+        install_skill(&skill, &config).unwrap();
+
+        let link_target = fs::read_link(target_dir.join("test-skill")).unwrap();
+        assert_eq!(link_target, canonical_dir.join("test-skill"));
+    }
+
     #[test]
     fn test_install_skill_with_copy() {
         let temp_dir = TempDir::new().unwrap();
@@ -288,6 +732,152 @@ mod tests {
         assert_eq!(content, skill.raw_content);
     }
 
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_with_hardlink() {
+        use std::os::unix::fs::MetadataExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join("target/skills");
+
+        let mut config = InstallConfig::new(canonical_dir.clone());
+        config.target_dirs.push(target_dir.clone());
+        config.mode = InstallMode::Hardlink;
+        config.fallback = Vec::new();
+
+        let skill = create_test_skill();
+        let result = install_skill(&skill, &config).unwrap();
+
+        let target_path = target_dir.join("test-skill");
+        assert!(!fs::symlink_metadata(&target_path)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+
+        let canonical_meta = fs::metadata(result.path.join("SKILL.md")).unwrap();
+        let target_meta = fs::metadata(target_path.join("SKILL.md")).unwrap();
+        assert_eq!(canonical_meta.ino(), target_meta.ino());
+
+        assert_eq!(
+            result.targets,
+            vec![TargetInstall {
+                path: target_path,
+                requested_mode: InstallMode::Hardlink,
+                effective_mode: InstallMode::Hardlink,
+                fallback_error: None,
+            }]
+        );
+        assert!(!result.symlink_failed);
+    }
+
+    #[test]
+    fn test_install_skill_with_reflink_falls_back_to_copy() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join("target/skills");
+
+        let mut config = InstallConfig::new(canonical_dir.clone());
+        config.target_dirs.push(target_dir.clone());
+        config.mode = InstallMode::Reflink;
+
+        let skill = create_test_skill();
+        let result = install_skill(&skill, &config).unwrap();
+
+        // Depending on the filesystem the clone either succeeds or falls back to copy
+        assert_eq!(result.targets.len(), 1);
+        let target = &result.targets[0];
+        assert_eq!(target.requested_mode, InstallMode::Reflink);
+        match target.effective_mode {
+            InstallMode::Reflink => assert!(target.fallback_error.is_none()),
+            InstallMode::Copy => {
+                let error = target.fallback_error.as_deref().unwrap();
+                assert!(error.starts_with("reflink: "), "got: {error}");
+            }
+            other => panic!("Unexpected mode: {other:?}"),
+        }
+
+        let content = fs::read_to_string(target_dir.join("test-skill/SKILL.md")).unwrap();
+        assert_eq!(content, skill.raw_content);
+    }
+
+    #[test]
+    fn test_install_skill_without_fallback_reports_error() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+
+        // A regular file where the target directory should be makes every mode fail
+        let target_dir = temp_dir.path().join("target");
+        fs::write(&target_dir, "not a directory").unwrap();
+
+        let mut config = InstallConfig::new(canonical_dir);
+        config.target_dirs.push(target_dir);
+        config.fallback = Vec::new();
+
+        let result = install_skill(&create_test_skill(), &config);
+        assert!(result.is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_replaces_dangling_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join("target/skills");
+        fs::create_dir_all(&target_dir).unwrap();
+        std::os::unix::fs::symlink(
+            temp_dir.path().join("missing"),
+            target_dir.join("test-skill"),
+        )
+        .unwrap();
+
+        let mut config = InstallConfig::new(canonical_dir);
+        config.target_dirs.push(target_dir.clone());
+        config.mode = InstallMode::Copy;
+
+        install_skill(&create_test_skill(), &config).unwrap();
+        assert!(target_dir.join("test-skill/SKILL.md").exists());
+    }
+
+    #[test]
+    fn test_install_renamed_skill_via_provider() {
+        /// Provider that fetches the SKILL.md as the source has it
+        struct SourceProvider;
+        impl SkillProvider for SourceProvider {
+            fn discover_skills(&self, _: &str, _: Option<&str>) -> anyhow::Result<Vec<Skill>> {
+                Ok(Vec::new())
+            }
+            fn discover_skills_at(
+                &self,
+                _: &str,
+                _: Option<&str>,
+                _: Option<&str>,
+            ) -> anyhow::Result<Vec<Skill>> {
+                Ok(Vec::new())
+            }
+            fn fetch_skill(&self, _: &Skill, dest: &Path) -> anyhow::Result<()> {
+                fs::create_dir_all(dest)?;
+                fs::write(dest.join("SKILL.md"), "---\nname: pdf\n---\n")?;
+                Ok(())
+            }
+            fn get_folder_hash(&self, _: &Skill) -> anyhow::Result<String> {
+                Ok(String::new())
+            }
+        }
+
+        let temp_dir = TempDir::new().unwrap();
+        let mut skill = create_test_skill();
+        skill.name = "claude-pdf".to_string();
+        let config = InstallConfig::new(temp_dir.path().to_path_buf());
+        let result = install_skill_with_provider(&skill, &config, Some(&SourceProvider)).unwrap();
+
+        assert_eq!(result.path, temp_dir.path().join("claude-pdf"));
+        assert_eq!(
+            fs::read_to_string(result.path.join("SKILL.md")).unwrap(),
+            "---\nname: claude-pdf\n---\n"
+        );
+    }
+
     #[test]
     fn test_install_skill_with_auxiliary_files() {
         use std::collections::HashMap;
@@ -329,6 +919,29 @@ mod tests {
         assert_eq!(guide_content, "# Guide\nContent");
     }
 
+    #[test]
+    fn test_install_skill_rejects_invalid_name() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join("target/skills");
+
+        // A directory the traversal name would resolve to must survive
+        let victim = temp_dir.path().join("victim");
+        fs::create_dir_all(&victim).unwrap();
+        fs::write(victim.join("keep.txt"), "keep").unwrap();
+
+        let mut skill = create_test_skill();
+        skill.name = "../../victim".to_string();
+
+        let mut config = InstallConfig::new(canonical_dir.clone());
+        config.target_dirs.push(target_dir);
+
+        let err = install_skill(&skill, &config).unwrap_err();
+        assert!(matches!(err, SkillsError::InvalidSkillName(_)));
+        assert!(victim.join("keep.txt").exists());
+        assert!(!canonical_dir.exists());
+    }
+
     #[test]
     fn test_install_skill_rejects_absolute_auxiliary_path() {
         use std::collections::HashMap;
@@ -392,7 +1005,9 @@ mod tests {
             result.is_err(),
             "Expected error for path traversal auxiliary path"
         );
-        let err = result.unwrap_err().to_string();
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), "unsafe_path");
+        let err = err.to_string();
         assert!(
             err.contains("must not traverse outside"),
             "Expected traversal error, got: {err}"
@@ -482,6 +1097,234 @@ mod tests {
         );
     }
 
+    #[cfg(unix)]
+    fn create_skill_with_aux(rel_path: &str) -> Skill {
+        let mut skill = create_test_skill();
+        skill
+            .auxiliary_files
+            .insert(rel_path.to_string(), "payload".to_string());
+        skill
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_contained_write_path_refuses_symlinked_dir_leaving_skill_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+
+        // Skill dir with `scripts -> <outside>`, as a provider fetch could leave it
+        let skill_dir = temp_dir.path().join("test-skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        std::os::unix::fs::symlink(&outside, skill_dir.join("scripts")).unwrap();
+
+        let err = contained_write_path(&skill_dir, Path::new("scripts/run.sh"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("leaves the skill directory"), "got: {err}");
+
+        // Missing directories below the symlink must not be created outside either
+        assert!(contained_write_path(&skill_dir, Path::new("scripts/nested/run.sh")).is_err());
+        assert!(!outside.join("nested").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_contained_write_path_refuses_symlinked_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside_file = temp_dir.path().join("outside.txt");
+        fs::write(&outside_file, "original").unwrap();
+        let missing = temp_dir.path().join("missing.txt");
+
+        let skill_dir = temp_dir.path().join("test-skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        std::os::unix::fs::symlink(&outside_file, skill_dir.join("SKILL.md")).unwrap();
+        std::os::unix::fs::symlink(&missing, skill_dir.join("notes.md")).unwrap();
+
+        assert!(contained_write_path(&skill_dir, Path::new("SKILL.md")).is_err());
+        let err = contained_write_path(&skill_dir, Path::new("notes.md"))
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("dangling symlink"), "got: {err}");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_contained_write_path_allows_symlink_within_skill_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let skill_dir = temp_dir.path().join("test-skill");
+        fs::create_dir_all(skill_dir.join("real")).unwrap();
+        std::os::unix::fs::symlink(skill_dir.join("real"), skill_dir.join("scripts")).unwrap();
+
+        let path = contained_write_path(&skill_dir, Path::new("scripts/run.sh")).unwrap();
+        assert_eq!(path, skill_dir.canonicalize().unwrap().join("real/run.sh"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replicate_tree_recreates_symlinks_within_skill_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let skill_dir = temp_dir.path().join("test-skill");
+        fs::create_dir_all(skill_dir.join("real")).unwrap();
+        fs::write(skill_dir.join("real/run.sh"), "echo hi").unwrap();
+        std::os::unix::fs::symlink("real", skill_dir.join("scripts")).unwrap();
+        std::os::unix::fs::symlink("../real/run.sh", skill_dir.join("real/again.sh")).unwrap();
+        // A link back to an ancestor is kept as a link rather than recursed into
+        std::os::unix::fs::symlink("..", skill_dir.join("real/up")).unwrap();
+
+        let copy = temp_dir.path().join("copy");
+        copy_skill(&skill_dir, &copy).unwrap();
+        assert_eq!(
+            fs::read_link(copy.join("scripts")).unwrap(),
+            Path::new("real")
+        );
+        assert_eq!(
+            fs::read_link(copy.join("real/up")).unwrap(),
+            Path::new("..")
+        );
+        assert_eq!(
+            fs::read_to_string(copy.join("scripts/again.sh")).unwrap(),
+            "echo hi"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_replicate_tree_refuses_symlinks_leaving_skill_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("secret"), "secret").unwrap();
+
+        let links: [(&str, PathBuf); 3] = [
+            ("absolute", outside.clone()),
+            ("relative", PathBuf::from("../outside")),
+            ("dangling", PathBuf::from("missing")),
+        ];
+        for (name, target) in links {
+            let skill_dir = temp_dir.path().join(name);
+            fs::create_dir_all(&skill_dir).unwrap();
+            fs::write(skill_dir.join("SKILL.md"), "content").unwrap();
+            std::os::unix::fs::symlink(&target, skill_dir.join("scripts")).unwrap();
+
+            let copy = temp_dir.path().join(format!("{name}-copy"));
+            let err = copy_skill(&skill_dir, &copy).unwrap_err();
+            assert_eq!(err.code(), "unsafe_path", "{name}: {err}");
+            assert!(!copy.join("scripts/secret").exists());
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_with_copy_refuses_escaping_symlink() {
+        let temp_dir = TempDir::new().unwrap();
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        let canonical_path = temp_dir.path().join(".agents/skills/test-skill");
+        let target_dir = temp_dir.path().join("target/skills");
+
+        let mut config = InstallConfig::new(temp_dir.path().join(".agents/skills"));
+        config.target_dirs.push(target_dir.clone());
+        config.mode = InstallMode::Copy;
+        config.fallback = vec![InstallMode::Symlink];
+
+        // Skill dir with `scripts -> <outside>`, as a provider fetch could leave it
+        install_skill(&create_test_skill(), &config).unwrap();
+        std::os::unix::fs::symlink(&outside, canonical_path.join("scripts")).unwrap();
+
+        // The symlink fallback isn't tried either
+        let err =
+            link_or_copy_skill(&canonical_path, &target_dir, "test-skill", &config).unwrap_err();
+        assert_eq!(err.code(), "unsafe_path");
+        assert!(fs::symlink_metadata(target_dir.join("test-skill")).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_replaces_stale_skill_dir() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        let outside_file = temp_dir.path().join("outside.txt");
+        fs::write(&outside_file, "original").unwrap();
+
+        // Stale skill dir with symlinks leaving it and a file the skill no longer has
+        let skill_dir = canonical_dir.join("test-skill");
+        fs::create_dir_all(skill_dir.join("references")).unwrap();
+        std::os::unix::fs::symlink(&outside, skill_dir.join("scripts")).unwrap();
+        std::os::unix::fs::symlink(&outside_file, skill_dir.join("references/guide.md")).unwrap();
+        std::os::unix::fs::symlink(&outside_file, skill_dir.join("SKILL.md")).unwrap();
+        fs::write(skill_dir.join("stale.md"), "old").unwrap();
+
+        let mut skill = create_skill_with_aux("scripts/nested/run.sh");
+        skill
+            .auxiliary_files
+            .insert("references/guide.md".to_string(), "guide".to_string());
+        let config = InstallConfig::new(canonical_dir);
+        install_skill(&skill, &config).unwrap();
+
+        assert!(!skill_dir.join("stale.md").exists());
+        assert!(!skill_dir.join("scripts").is_symlink());
+        assert_eq!(
+            fs::read_to_string(skill_dir.join("scripts/nested/run.sh")).unwrap(),
+            "payload"
+        );
+        assert!(fs::read_dir(&outside).unwrap().next().is_none());
+        assert_eq!(fs::read_to_string(&outside_file).unwrap(), "original");
+        assert_eq!(
+            crate::lock::compute_skill_hash(&skill_dir).unwrap(),
+            crate::lock::compute_content_hash(&skill)
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_install_skill_refuses_symlinked_skill_root() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(&canonical_dir).unwrap();
+        std::os::unix::fs::symlink(&outside, canonical_dir.join("test-skill")).unwrap();
+
+        let skill = create_skill_with_aux("scripts/run.sh");
+        let config = InstallConfig::new(canonical_dir);
+        let err = install_skill(&skill, &config).unwrap_err();
+
+        assert_eq!(err.code(), "unsafe_path");
+        assert!(!outside.join("SKILL.md").exists());
+        assert!(!outside.join("scripts").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_provider_fetch_refuses_symlinked_destination() {
+        use crate::providers::MockProvider;
+
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let outside = temp_dir.path().join("outside");
+        fs::create_dir_all(&outside).unwrap();
+        fs::create_dir_all(&canonical_dir).unwrap();
+        std::os::unix::fs::symlink(&outside, canonical_dir.join("test-skill")).unwrap();
+
+        let skill = create_test_skill();
+        let provider = MockProvider::new(vec![skill.clone()]);
+        let config = InstallConfig::new(canonical_dir.clone());
+        let err = install_skill_with_provider(&skill, &config, Some(&provider)).unwrap_err();
+        assert_eq!(err.code(), "unsafe_path");
+        assert!(!outside.join("SKILL.md").exists());
+
+        // Nor through a symlinked install directory
+        let linked_dir = temp_dir.path().join("linked-skills");
+        std::os::unix::fs::symlink(&outside, &linked_dir).unwrap();
+        let config = InstallConfig::new(linked_dir);
+        let err = install_skill_with_provider(&skill, &config, Some(&provider)).unwrap_err();
+        assert_eq!(err.code(), "unsafe_path");
+        assert!(!outside.join("test-skill").exists());
+    }
+
     #[test]
     fn test_embedded_skill_installation() {
         let temp_dir = TempDir::new().unwrap();
@@ -499,4 +1342,30 @@ mod tests {
         assert!(result.path.join("SKILL.md").exists());
         assert!(!result.symlink_failed);
     }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_uninstall_skill_removes_canonical_and_targets() {
+        let temp_dir = TempDir::new().unwrap();
+        let canonical_dir = temp_dir.path().join(".agents/skills");
+        let target_dir = temp_dir.path().join(".claude/skills");
+
+        let mut config = InstallConfig::new(canonical_dir.clone());
+        config.target_dirs = vec![target_dir.clone()];
+        install_skill(&create_test_skill(), &config).unwrap();
+
+        let removed = uninstall_skill("test-skill", &config).unwrap();
+        assert_eq!(
+            removed,
+            vec![
+                target_dir.join("test-skill"),
+                canonical_dir.join("test-skill")
+            ]
+        );
+        assert!(!canonical_dir.join("test-skill").exists());
+        assert!(fs::symlink_metadata(target_dir.join("test-skill")).is_err());
+
+        assert!(uninstall_skill("test-skill", &config).unwrap().is_empty());
+        assert!(uninstall_skill("../escape", &config).is_err());
+    }
 }
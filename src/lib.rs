@@ -1,20 +1,42 @@
 pub mod cli;
 pub mod discovery;
 pub mod embedded;
+pub mod error;
 pub mod installer;
 pub mod lock;
+pub mod manifest;
+pub mod paths;
 pub mod providers;
+pub mod scope;
+pub mod skills_cli;
 pub mod types;
 
-pub use cli::{get_command_schema, get_commands, output_commands_json};
-pub use discovery::{discover_skills, discover_skills_with_provider, DiscoveryConfig};
+pub use cli::{
+    get_command_events_schema, get_command_result_schema, get_command_schema, get_commands,
+    get_lock_schema, output_commands_json,
+};
+pub use discovery::{
+    discover_skills, discover_skills_with_provider, discover_skills_with_report,
+    find_duplicate_skills, DiscoveryConfig, DiscoveryReport, DuplicatePolicy, DuplicateSkill,
+    InvalidSkill,
+};
 pub use embedded::{get_embedded_skill, register_embedded_skill};
+pub use error::{Result, SkillsError};
 pub use installer::{
-    install_skill, install_skill_with_provider, InstallConfig, InstallMode, InstallResult,
+    install_skill, install_skill_with_provider, uninstall_skill, unlink_skill, InstallConfig,
+    InstallMode, InstallResult, LinkStyle,
 };
-pub use lock::LockManager;
+pub use lock::{
+    diagnose_lock, find_lock_conflicts, install_frozen, DoctorReport, LockFormat, LockIssue,
+    LockManager,
+};
+pub use manifest::{plan_sync, Manifest, ManifestSource, PlannedSkill, SyncPlan, MANIFEST_PATH};
+pub use paths::Paths;
 pub use providers::{MockProvider, SkillProvider};
-pub use types::{Skill, SkillLock, Source, SourceType};
+pub use scope::{combined_view, CombinedView, Scope, ScopeKind, ScopedSkill};
+pub use types::{
+    validate_skill_name, InvalidSkillName, Skill, SkillFilter, SkillLock, Source, SourceType,
+};
 
 #[cfg(test)]
 mod integration_tests {
@@ -1,46 +1,183 @@
-use crate::types::{LockEntry, SkillLock, Source};
-use anyhow::{Context, Result};
+use crate::discovery::{discover_skills_with_provider, DiscoveryConfig, DuplicateSkill};
+use crate::error::{Result, SkillsError};
+use crate::installer::{install_skill_with_provider, InstallConfig, InstallResult};
+use crate::providers::SkillProvider;
+use crate::skills_cli;
+use crate::types::{
+    LockEntry, LockFormatError, PreviousSource, Skill, SkillFilter, SkillLock, Source, SourceType,
+    HASH_VERSION, LOCK_VERSION,
+};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::fs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
+
+/// Format [`LockManager::save`] writes the lock file in; both are read regardless
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum LockFormat {
+    /// Keep the format of the existing file, or use this crate's for a new one
+    #[default]
+    Auto,
+    /// This crate's format
+    Native,
+    /// The format of the vercel-labs `skills` CLI (integer version 3)
+    SkillsCli,
+}
+
+/// Lock file as read from disk, before strict validation
+struct ReadLock {
+    lock: SkillLock,
+    /// Whether the file is in the legacy format and needs rewriting
+    legacy: bool,
+    /// Values left out of `lock` because they don't follow the lock format
+    violations: Vec<LockFormatError>,
+}
 
 /// Lock file manager
 pub struct LockManager {
     lock_path: PathBuf,
+    format: LockFormat,
 }
 
 impl LockManager {
     pub fn new(lock_path: PathBuf) -> Self {
-        Self { lock_path }
+        Self {
+            lock_path,
+            format: LockFormat::default(),
+        }
+    }
+
+    fn lock_dir(&self) -> &Path {
+        self.lock_path.parent().unwrap_or(Path::new("."))
     }
 
-    /// Load lock file, creating new if it doesn't exist
+    /// Write the lock file in `format`
+    pub fn with_format(mut self, format: LockFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Load lock file, creating new if it doesn't exist.
+    ///
+    /// A lock file in the legacy format is migrated in memory; the file itself is only
+    /// rewritten by the next [`save`](Self::save), so reading never modifies it.
+    ///
+    /// Entries whose skill name is invalid are left out rather than failing, since they
+    /// can't be installed anyway; [`doctor`](Self::doctor) reports them.
     pub fn load(&self) -> Result<SkillLock> {
+        let read = self.read()?;
+        let mut violations = read
+            .violations
+            .into_iter()
+            .filter(|error| !error.invalid_name);
+        match violations.next() {
+            Some(violation) => Err(self.invalid(violation)),
+            None => Ok(read.lock),
+        }
+    }
+
+    fn invalid(&self, error: LockFormatError) -> SkillsError {
+        SkillsError::LockInvalid {
+            path: self.lock_path.clone(),
+            field: error.field,
+            reason: error.reason,
+        }
+    }
+
+    /// Parse the lock file leniently; see [`SkillLock::from_value_lenient`]
+    fn read(&self) -> Result<ReadLock> {
         if !self.lock_path.exists() {
-            return Ok(SkillLock::new());
+            return Ok(ReadLock {
+                lock: SkillLock::new(),
+                legacy: false,
+                violations: Vec::new(),
+            });
         }
 
         let content = fs::read_to_string(&self.lock_path)
-            .with_context(|| format!("Failed to read lock file: {:?}", self.lock_path))?;
+            .map_err(SkillsError::io("read lock file", &self.lock_path))?;
 
-        let lock: SkillLock = serde_json::from_str(&content)
-            .with_context(|| format!("Failed to parse lock file: {:?}", self.lock_path))?;
+        let value: serde_json::Value =
+            serde_json::from_str(&content).map_err(|source| SkillsError::LockCorrupt {
+                path: self.lock_path.clone(),
+                source,
+            })?;
 
-        Ok(lock)
+        let legacy = SkillLock::is_legacy(&value);
+        let imported = skills_cli::is_skills_cli_lock(&value);
+        let parsed = if imported {
+            skills_cli::import_lock_lenient(value, self.lock_dir())
+        } else {
+            SkillLock::from_value_lenient(value)
+        };
+        let (lock, violations) = parsed.map_err(|error| self.invalid(error))?;
+
+        Ok(ReadLock {
+            lock,
+            legacy,
+            violations,
+        })
     }
 
-    /// Save lock file
+    /// Save lock file in the configured [`LockFormat`]
+    ///
+    /// Entries are written sorted by skill name with a trailing newline, and the file
+    /// is left untouched when its content wouldn't change.
+    ///
+    /// Legacy entries carry no hash, and `skills` CLI entries none of ours until this
+    /// crate writes them; those still installed get one, those that can't be read don't.
     pub fn save(&self, lock: &SkillLock) -> Result<()> {
-        if let Some(parent) = self.lock_path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create lock directory: {:?}", parent))?;
+        let mut lock = lock.clone();
+        for entry in lock.skills.values_mut() {
+            let path = Path::new(&entry.skill_path);
+            if entry.skill_folder_hash.is_empty() && path.is_dir() {
+                if let Ok(hash) = compute_skill_hash(path) {
+                    entry.skill_folder_hash = hash;
+                    entry.hash_version = HASH_VERSION;
+                }
+            }
         }
 
-        let content =
-            serde_json::to_string_pretty(lock).context("Failed to serialize lock file")?;
+        let existing = fs::read_to_string(&self.lock_path).ok();
+        let existing_value = existing
+            .as_deref()
+            .and_then(|content| serde_json::from_str::<serde_json::Value>(content).ok());
+
+        let skills_cli = match self.format {
+            LockFormat::Auto => existing_value
+                .as_ref()
+                .is_some_and(skills_cli::is_skills_cli_lock),
+            LockFormat::Native => false,
+            LockFormat::SkillsCli => true,
+        };
+        let serialized = if skills_cli {
+            let exported = skills_cli::export_lock(&lock, existing_value.as_ref(), self.lock_dir());
+            serde_json::to_string_pretty(&exported)
+        } else if lock.version == LOCK_VERSION {
+            serde_json::to_string_pretty(&lock)
+        } else {
+            // A lock imported from the `skills` CLI keeps its version until written natively
+            lock.version = LOCK_VERSION.to_string();
+            serde_json::to_string_pretty(&lock)
+        };
+        let mut content = serialized.map_err(|source| SkillsError::Serialize {
+            what: "lock file",
+            source,
+        })?;
+        content.push('\n');
+
+        if existing.is_some_and(|existing| existing == content) {
+            return Ok(());
+        }
+
+        if let Some(parent) = self.lock_path.parent() {
+            fs::create_dir_all(parent).map_err(SkillsError::io("create lock directory", parent))?;
+        }
 
         fs::write(&self.lock_path, content)
-            .with_context(|| format!("Failed to write lock file: {:?}", self.lock_path))?;
+            .map_err(SkillsError::io("write lock file", &self.lock_path))?;
 
         Ok(())
     }
@@ -59,41 +196,118 @@ impl LockManager {
         skill_path: &Path,
         folder_hash: String,
     ) -> Result<()> {
+        self.upsert_entry(skill_name, source, skill_path, folder_hash, None, None)?;
+        Ok(())
+    }
+
+    /// Update or add the entry for an installed skill, recording where in the source it
+    /// was found and, for sources fetched through `provider`, the commit the source's
+    /// ref resolves to.
+    ///
+    /// Returns the previous source when the skill was installed from a different origin
+    /// before; it is kept in the entry's `history`.
+    pub fn update_skill_entry(
+        &self,
+        skill: &Skill,
+        source: &Source,
+        skill_path: &Path,
+        folder_hash: String,
+        provider: Option<&dyn SkillProvider>,
+    ) -> Result<Option<PreviousSource>> {
+        let commit = match (provider, &source.url) {
+            (Some(provider), Some(url)) if source.source_type.requires_provider() => provider
+                .resolve_commit(url, source.ref_.as_deref())
+                .map_err(|error| SkillsError::Provider {
+                    context: format!("Failed to resolve commit of skill {:?}", skill.name),
+                    error,
+                })?,
+            _ => None,
+        };
+        self.upsert_entry(
+            &skill.name,
+            source,
+            skill_path,
+            folder_hash,
+            commit,
+            skill_relative_path(skill, source),
+        )
+    }
+
+    fn upsert_entry(
+        &self,
+        skill_name: &str,
+        source: &Source,
+        skill_path: &Path,
+        folder_hash: String,
+        commit: Option<String>,
+        relative_path: Option<String>,
+    ) -> Result<Option<PreviousSource>> {
         let mut lock = self.load()?;
         let now = chrono::Utc::now();
 
-        // Normalize source type for embedded/self
-        let normalized_source_type = if source.source_type.is_embedded() {
-            "self".to_string()
+        let source_name = if source.source_type.is_embedded() {
+            "Self".to_string()
         } else {
-            format!("{:?}", source.source_type).to_lowercase()
+            format!("{:?}", source.source_type)
         };
+        let source_type = lock_source_type(source);
 
         let entry = lock
             .skills
             .entry(skill_name.to_string())
             .or_insert_with(|| LockEntry {
-                source: if source.source_type.is_embedded() {
-                    "Self".to_string()
-                } else {
-                    format!("{:?}", source.source_type)
-                },
-                source_type: normalized_source_type.clone(),
+                source: source_name.clone(),
+                source_type: source_type.clone(),
                 source_url: source.url.clone(),
                 skill_path: skill_path.to_string_lossy().to_string(),
                 skill_folder_hash: folder_hash.clone(),
+                hash_version: HASH_VERSION,
                 installed_at: now,
                 updated_at: now,
+                ref_: None,
+                commit: None,
+                subpath: None,
+                relative_path: None,
+                history: Vec::new(),
             });
 
-        // Update entry
-        entry.source_type = normalized_source_type;
+        let previous = (!same_origin(entry, source)).then(|| PreviousSource {
+            source: entry.source.clone(),
+            source_type: entry.source_type.clone(),
+            source_url: entry.source_url.clone(),
+            ref_: entry.ref_.clone(),
+            commit: entry.commit.clone(),
+            subpath: entry.subpath.clone(),
+            replaced_at: now,
+        });
+        if let Some(previous) = &previous {
+            entry.history.push(previous.clone());
+        }
+
+        let unchanged = entry.clone();
+
+        // Rewrite the whole provenance so nothing from the previous source lingers
+        entry.source = source_name;
+        entry.source_type = source_type;
+        entry.source_url = source.url.clone();
         entry.skill_folder_hash = folder_hash;
+        entry.hash_version = HASH_VERSION;
         entry.updated_at = now;
         entry.skill_path = skill_path.to_string_lossy().to_string();
+        entry.ref_ = source.ref_.clone();
+        entry.commit = commit;
+        entry.subpath = source.subpath.clone();
+        entry.relative_path = relative_path;
+
+        // Reinstalling identical content keeps the entry, and the file, as they were
+        let mut same = entry.clone();
+        same.updated_at = unchanged.updated_at;
+        if same == unchanged {
+            *entry = unchanged;
+        }
 
         self.save(&lock)?;
-        Ok(())
+        Ok(previous)
     }
 
     /// Get entry for a skill
@@ -109,22 +323,403 @@ impl LockManager {
         self.save(&lock)?;
         Ok(())
     }
+
+    /// Check every entry for problems and, unless `dry_run`, repair those that can be.
+    ///
+    /// The lock file is read leniently, so values that don't follow the lock format are
+    /// reported (and dropped when repairing) instead of failing. A legacy lock file is
+    /// rewritten in the current format, entries without a hash or with one computed by
+    /// an older [`HASH_VERSION`] get their hash recomputed, and entries whose installed
+    /// directory is gone are removed.
+    pub fn doctor(&self, dry_run: bool) -> Result<DoctorReport> {
+        let ReadLock {
+            mut lock,
+            legacy,
+            violations,
+        } = self.read()?;
+        let mut report = DoctorReport::default();
+
+        let mut issues = Vec::new();
+        if legacy {
+            issues.push(LockIssue::LegacyFormat);
+        }
+        issues.extend(violations.into_iter().map(|error| LockIssue::InvalidField {
+            field: error.field,
+            reason: error.reason,
+        }));
+        issues.extend(diagnose_lock(&lock));
+        for issue in issues {
+            if dry_run || !issue.is_fixable() {
+                report.remaining.push(issue);
+                continue;
+            }
+            match &issue {
+                LockIssue::MissingHash { skill } | LockIssue::OutdatedHash { skill, .. } => {
+                    if let Some(entry) = lock.skills.get_mut(skill) {
+                        entry.skill_folder_hash = compute_skill_hash(Path::new(&entry.skill_path))?;
+                        entry.hash_version = HASH_VERSION;
+                    }
+                }
+                LockIssue::MissingPath { skill, .. } => {
+                    lock.skills.remove(skill);
+                }
+                // Already left out of `lock`, or rewritten by the save below
+                LockIssue::LegacyFormat
+                | LockIssue::InvalidField { .. }
+                | LockIssue::UnknownSourceType { .. } => {}
+            }
+            report.fixed.push(issue);
+        }
+
+        if !report.fixed.is_empty() {
+            self.save(&lock)?;
+        }
+        Ok(report)
+    }
+}
+
+/// Find problems in the entries of a lock that parsed successfully
+pub fn diagnose_lock(lock: &SkillLock) -> Vec<LockIssue> {
+    let mut issues = Vec::new();
+    for (name, entry) in &lock.skills {
+        // An entry for a skill that isn't installed, or can't be read, is dropped, whatever
+        // else is wrong with it
+        if fs::read_dir(&entry.skill_path).is_err() {
+            issues.push(LockIssue::MissingPath {
+                skill: name.clone(),
+                path: entry.skill_path.clone(),
+            });
+            continue;
+        }
+        let source = entry_source(entry);
+        if source.is_err() {
+            issues.push(LockIssue::UnknownSourceType {
+                skill: name.clone(),
+                source_type: entry.source_type.clone(),
+            });
+        }
+        if entry.skill_folder_hash.is_empty() {
+            issues.push(LockIssue::MissingHash {
+                skill: name.clone(),
+            });
+        } else if entry.hash_version < HASH_VERSION
+            // Providers hash skills their own way, whatever the version
+            && source.is_ok_and(|source| !source.source_type.requires_provider())
+        {
+            issues.push(LockIssue::OutdatedHash {
+                skill: name.clone(),
+                hash_version: entry.hash_version,
+            });
+        }
+    }
+    issues
+}
+
+/// Problem found in a lock entry by [`LockManager::doctor`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "issue", rename_all = "kebab-case")]
+pub enum LockIssue {
+    /// The lock file is in the legacy format, whose `skills` is a list
+    LegacyFormat,
+    /// A value doesn't follow the lock format; repairing drops it, or its whole entry
+    /// when the entry can't be read
+    InvalidField { field: String, reason: String },
+    /// The entry has no content hash, as left by migrating a legacy lock file
+    MissingHash { skill: String },
+    /// The entry's hash was computed by an older [`HASH_VERSION`]; it still verifies, but
+    /// only the files that version hashed
+    OutdatedHash { skill: String, hash_version: u32 },
+    /// The installed skill directory recorded in the entry doesn't exist
+    MissingPath { skill: String, path: String },
+    /// The entry's source type isn't one this version knows
+    UnknownSourceType { skill: String, source_type: String },
+}
+
+impl LockIssue {
+    /// Whether [`LockManager::doctor`] can repair the issue
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, LockIssue::UnknownSourceType { .. })
+    }
+}
+
+impl std::fmt::Display for LockIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockIssue::LegacyFormat => write!(f, "lock file uses the legacy format"),
+            LockIssue::InvalidField { field, reason } => write!(f, "{}: {}", field, reason),
+            LockIssue::MissingHash { skill } => write!(f, "{}: missing content hash", skill),
+            LockIssue::OutdatedHash {
+                skill,
+                hash_version,
+            } => write!(
+                f,
+                "{}: content hash uses version {} instead of {}",
+                skill, hash_version, HASH_VERSION
+            ),
+            LockIssue::MissingPath { skill, path } => {
+                write!(f, "{}: installed directory {} not found", skill, path)
+            }
+            LockIssue::UnknownSourceType { skill, source_type } => {
+                write!(f, "{}: unknown source type {:?}", skill, source_type)
+            }
+        }
+    }
+}
+
+/// Outcome of [`LockManager::doctor`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DoctorReport {
+    /// Issues that were repaired
+    pub fixed: Vec<LockIssue>,
+    /// Issues left in the lock file, because they can't be repaired or of a dry run
+    pub remaining: Vec<LockIssue>,
 }
 
-/// Compute hash for a skill directory
+/// Reinstall every skill recorded in the lock exactly as recorded.
+///
+/// Each skill is discovered from its recorded source at the recorded commit (or ref),
+/// and its hash and commit are compared with the entry before anything is written,
+/// so a mismatch leaves installed skills untouched. The lock itself is never modified.
+pub fn install_frozen(
+    lock: &SkillLock,
+    config: &InstallConfig,
+    discovery: &DiscoveryConfig,
+    provider: Option<&dyn SkillProvider>,
+) -> Result<Vec<(Skill, InstallResult)>> {
+    let mut verified = Vec::new();
+    for (name, entry) in &lock.skills {
+        let recorded = entry_source(entry)?;
+        let source = Source {
+            // The recorded directory narrows discovery to exactly the installed skill
+            subpath: entry.relative_path.clone().or(recorded.subpath),
+            ref_: entry.commit.clone().or(recorded.ref_),
+            skill_filter: Some(SkillFilter::new(&[name.as_str()], &[])),
+            ..recorded
+        };
+        let provider = match provider {
+            Some(provider) if source.source_type.requires_provider() => Some(provider),
+            None if source.source_type.requires_provider() => {
+                return Err(SkillsError::InvalidSource {
+                    reason: format!(
+                        "No provider available for {} source of skill {}",
+                        entry.source_type, name
+                    ),
+                })
+            }
+            _ => None,
+        };
+
+        let skill = discover_skills_with_provider(&source, discovery, provider)?
+            .into_iter()
+            .find(|skill| &skill.name == name)
+            .ok_or_else(|| SkillsError::SkillNotFound { name: name.clone() })?;
+
+        let provider_error = |error| SkillsError::Provider {
+            context: format!("Failed to verify skill {:?}", name),
+            error,
+        };
+        let actual_hash = match provider {
+            Some(provider) => provider.get_folder_hash(&skill).map_err(provider_error)?,
+            None => compute_versioned_content_hash(&skill, entry.hash_version),
+        };
+        if actual_hash != entry.skill_folder_hash {
+            return Err(SkillsError::LockMismatch {
+                name: name.clone(),
+                field: "skillFolderHash",
+                expected: entry.skill_folder_hash.clone(),
+                actual: actual_hash,
+            });
+        }
+
+        if let (Some(provider), Some(expected), Some(url)) = (provider, &entry.commit, &source.url)
+        {
+            let actual = provider
+                .resolve_commit(url, source.ref_.as_deref())
+                .map_err(provider_error)?;
+            if actual.as_ref() != Some(expected) {
+                return Err(SkillsError::LockMismatch {
+                    name: name.clone(),
+                    field: "commit",
+                    expected: expected.clone(),
+                    actual: actual.unwrap_or_else(|| "unknown".to_string()),
+                });
+            }
+        }
+
+        verified.push((skill, provider));
+    }
+
+    verified
+        .into_iter()
+        .map(|(skill, provider)| {
+            install_skill_with_provider(&skill, config, provider).map(|result| (skill, result))
+        })
+        .collect()
+}
+
+/// Rebuild the source a lock entry was installed from
+fn entry_source(entry: &LockEntry) -> Result<Source> {
+    let source_type: SourceType = serde_json::from_value(serde_json::Value::String(
+        entry.source_type.clone(),
+    ))
+    .map_err(|_| SkillsError::InvalidSource {
+        reason: format!("Unknown source type in lock: {}", entry.source_type),
+    })?;
+    Ok(Source {
+        source_type,
+        url: entry.source_url.clone(),
+        subpath: entry.subpath.clone(),
+        skill_filter: None,
+        ref_: entry.ref_.clone(),
+    })
+}
+
+/// Whether `entry` was installed from `source`, whatever ref or commit
+fn same_origin(entry: &LockEntry, source: &Source) -> bool {
+    entry.source_type == lock_source_type(source)
+        && entry.source_url == source.url
+        && entry.subpath == source.subpath
+}
+
+/// Skills about to be installed from `source` whose name the lock records for another
+/// source; installing them would replace that skill.
+///
+/// `kept` is the installed skill's directory and `duplicate` the new skill's SKILL.md.
+pub fn find_lock_conflicts(
+    lock: &SkillLock,
+    skills: &[Skill],
+    source: &Source,
+) -> Vec<DuplicateSkill> {
+    skills
+        .iter()
+        .filter_map(|skill| {
+            let entry = lock.skills.get(&skill.name)?;
+            (!same_origin(entry, source)).then(|| DuplicateSkill {
+                name: skill.name.clone(),
+                kept: Some(entry.skill_path.clone()),
+                duplicate: skill.path.clone(),
+                renamed_to: None,
+            })
+        })
+        .collect()
+}
+
+/// Source type as recorded in `LockEntry::source_type`
+pub(crate) fn lock_source_type(source: &Source) -> String {
+    // Normalize source type for embedded/self
+    if source.source_type.is_embedded() {
+        "self".to_string()
+    } else {
+        format!("{:?}", source.source_type).to_lowercase()
+    }
+}
+
+/// Directory of `skill` relative to the root of `source`, using `/` separators.
+///
+/// Embedded skills have no such directory; provider skills are expected to report
+/// paths relative to the repository root.
+pub fn skill_relative_path(skill: &Skill, source: &Source) -> Option<String> {
+    if source.source_type.is_embedded() {
+        return None;
+    }
+    let path = Path::new(skill.path.as_deref()?);
+    let dir = if path.file_name() == Some(std::ffi::OsStr::new("SKILL.md")) {
+        path.parent()?
+    } else {
+        path
+    };
+    let relative = match source.source_type {
+        SourceType::Local => {
+            let root = Path::new(source.url.as_deref().unwrap_or("."));
+            dir.strip_prefix(root).ok()?
+        }
+        _ if dir.is_relative() => dir,
+        _ => return None,
+    };
+    let components: Vec<_> = relative
+        .components()
+        .filter_map(|c| match c {
+            std::path::Component::Normal(part) => Some(part.to_string_lossy()),
+            _ => None,
+        })
+        .collect();
+    if components.is_empty() {
+        return Some(".".to_string());
+    }
+    Some(components.join("/"))
+}
+
+/// Compute the hash [`compute_skill_hash`] will record once `skill` is installed
+pub fn compute_content_hash(skill: &Skill) -> String {
+    let mut files = BTreeMap::new();
+    files.insert("SKILL.md".to_string(), skill.raw_content.as_bytes());
+    for (rel_path, content) in &skill.auxiliary_files {
+        files.insert(normalize_rel_path(Path::new(rel_path)), content.as_bytes());
+    }
+    hash_files(files)
+}
+
+/// Compute the hash of `skill` the way [`HASH_VERSION`] `hash_version` did, so entries
+/// recorded by an older version still verify
+pub fn compute_versioned_content_hash(skill: &Skill, hash_version: u32) -> String {
+    match hash_version {
+        1 => format!("{:x}", Sha256::digest(skill.raw_content.as_bytes())),
+        _ => compute_content_hash(skill),
+    }
+}
+
+/// Compute the hash of everything in a skill directory, matching [`compute_content_hash`]
+/// of the skill installed there. Symlinked directories are skipped.
 pub fn compute_skill_hash(skill_path: &Path) -> Result<String> {
-    let mut hasher = Sha256::new();
+    let mut files = BTreeMap::new();
+    if skill_path.is_dir() {
+        collect_files(skill_path, skill_path, &mut files)?;
+    }
+    Ok(hash_files(
+        files
+            .iter()
+            .map(|(path, content)| (path.clone(), content.as_slice())),
+    ))
+}
 
-    // For embedded skills, hash the SKILL.md content
-    let skill_file = skill_path.join("SKILL.md");
-    if skill_file.exists() {
-        let content = fs::read(&skill_file)
-            .with_context(|| format!("Failed to read skill file: {:?}", skill_file))?;
-        hasher.update(&content);
+fn collect_files(root: &Path, dir: &Path, files: &mut BTreeMap<String, Vec<u8>>) -> Result<()> {
+    let entries = fs::read_dir(dir).map_err(SkillsError::io("read skill directory", dir))?;
+    for entry in entries {
+        let entry = entry.map_err(SkillsError::io("read skill directory", dir))?;
+        let path = entry.path();
+        let file_type = entry
+            .file_type()
+            .map_err(SkillsError::io("inspect path", &path))?;
+        if file_type.is_dir() {
+            collect_files(root, &path, files)?;
+        } else if path.is_file() {
+            let content = fs::read(&path).map_err(SkillsError::io("read skill file", &path))?;
+            let rel_path = path.strip_prefix(root).unwrap_or(&path);
+            files.insert(normalize_rel_path(rel_path), content);
+        }
     }
+    Ok(())
+}
+
+/// `/`-separated form of a path inside a skill, so hashes don't depend on the platform
+fn normalize_rel_path(rel_path: &Path) -> String {
+    rel_path
+        .components()
+        .filter(|c| !matches!(c, Component::CurDir))
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
 
-    let result = hasher.finalize();
-    Ok(format!("{:x}", result))
+/// Hash files in path order: each file contributes its path and the hash of its content
+fn hash_files<'a>(files: impl IntoIterator<Item = (String, &'a [u8])>) -> String {
+    let mut hasher = Sha256::new();
+    for (path, content) in files {
+        hasher.update(path.as_bytes());
+        hasher.update([0]);
+        hasher.update(Sha256::digest(content));
+    }
+    format!("{:x}", hasher.finalize())
 }
 
 #[cfg(test)]
@@ -162,8 +757,14 @@ mod tests {
                 source_url: None,
                 skill_path: "/path/to/skill".to_string(),
                 skill_folder_hash: "abc123".to_string(),
+                hash_version: HASH_VERSION,
                 installed_at: now,
                 updated_at: now,
+                ref_: None,
+                commit: None,
+                subpath: None,
+                relative_path: None,
+                history: Vec::new(),
             },
         );
 
@@ -201,6 +802,344 @@ mod tests {
         assert!(!entry.skill_folder_hash.is_empty());
     }
 
+    #[test]
+    fn test_lock_file_is_sorted_and_stable() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+
+        let manager = LockManager::new(lock_path.clone());
+        let source = Source {
+            source_type: SourceType::Self_,
+            url: None,
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+        for name in ["zeta", "alpha", "mid"] {
+            manager.update_entry(name, &source, &skill_dir).unwrap();
+        }
+
+        let content = fs::read_to_string(&lock_path).unwrap();
+        assert!(content.ends_with("}\n"));
+        let positions: Vec<usize> = ["\"alpha\"", "\"mid\"", "\"zeta\""]
+            .iter()
+            .map(|key| content.find(key).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+
+        // Reinstalling the same content leaves the file byte-for-byte identical
+        let modified = fs::metadata(&lock_path).unwrap().modified().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        manager.update_entry("mid", &source, &skill_dir).unwrap();
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), content);
+        assert_eq!(
+            fs::metadata(&lock_path).unwrap().modified().unwrap(),
+            modified
+        );
+    }
+
+    #[test]
+    fn test_doctor_repairs_recoverable_issues() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+
+        let entry = |path: &Path, source_type: &str| LockEntry {
+            source: source_type.to_string(),
+            source_type: source_type.to_string(),
+            source_url: None,
+            skill_path: path.display().to_string(),
+            skill_folder_hash: String::new(),
+            hash_version: HASH_VERSION,
+            installed_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            ref_: None,
+            commit: None,
+            subpath: None,
+            relative_path: None,
+            history: Vec::new(),
+        };
+        let mut lock = SkillLock::new();
+        lock.skills
+            .insert("kept".to_string(), entry(&skill_dir, "self"));
+        lock.skills.insert(
+            "gone".to_string(),
+            entry(&temp_dir.path().join("gone"), "self"),
+        );
+        lock.skills
+            .insert("odd".to_string(), entry(&skill_dir, "svn"));
+        // Saving would fill in the hashes, so write the lock as another tool left it
+        fs::write(&lock_path, serde_json::to_string(&lock).unwrap()).unwrap();
+        let manager = LockManager::new(lock_path.clone());
+
+        let report = manager.doctor(true).unwrap();
+        assert!(report.fixed.is_empty());
+        assert_eq!(report.remaining.len(), 4);
+
+        let report = manager.doctor(false).unwrap();
+        assert_eq!(
+            report.fixed,
+            vec![
+                LockIssue::MissingPath {
+                    skill: "gone".to_string(),
+                    path: temp_dir.path().join("gone").display().to_string(),
+                },
+                LockIssue::MissingHash {
+                    skill: "kept".to_string()
+                },
+                LockIssue::MissingHash {
+                    skill: "odd".to_string()
+                },
+            ]
+        );
+        assert_eq!(
+            report.remaining,
+            vec![LockIssue::UnknownSourceType {
+                skill: "odd".to_string(),
+                source_type: "svn".to_string(),
+            }]
+        );
+
+        let lock = manager.load().unwrap();
+        assert!(!lock.skills.contains_key("gone"));
+        assert_eq!(
+            lock.skills["kept"].skill_folder_hash,
+            compute_skill_hash(&skill_dir).unwrap()
+        );
+        assert_eq!(manager.doctor(false).unwrap().fixed, vec![]);
+    }
+
+    #[test]
+    fn test_doctor_diagnoses_lock_that_fails_strict_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let manager = LockManager::new(lock_path.clone());
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+        let source = Source {
+            source_type: SourceType::Self_,
+            url: None,
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+        manager.update_entry("kept", &source, &skill_dir).unwrap();
+
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&lock_path).unwrap()).unwrap();
+        value["skills"]["kept"]["extra"] = serde_json::json!(true);
+        value["skills"]["My Skill"] = value["skills"]["kept"].clone();
+        value["skills"]["broken"] = serde_json::json!({"source": 1});
+        fs::write(&lock_path, value.to_string()).unwrap();
+        assert_eq!(manager.load().unwrap_err().code(), "lock_invalid");
+
+        let fields = |issues: &[LockIssue]| -> Vec<String> {
+            issues
+                .iter()
+                .map(|issue| match issue {
+                    LockIssue::InvalidField { field, .. } => field.clone(),
+                    other => panic!("unexpected issue {:?}", other),
+                })
+                .collect()
+        };
+        let report = manager.doctor(true).unwrap();
+        assert_eq!(
+            fields(&report.remaining),
+            vec![
+                "skills.My Skill",
+                "skills.broken.source",
+                "skills.kept.extra"
+            ]
+        );
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), value.to_string());
+
+        let report = manager.doctor(false).unwrap();
+        assert_eq!(report.fixed.len(), 3);
+        let lock = manager.load().unwrap();
+        assert_eq!(lock.skills.keys().collect::<Vec<_>>(), vec!["kept"]);
+    }
+
+    #[test]
+    fn test_legacy_lock_migrated_on_write_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+
+        let legacy = serde_json::json!({
+            "skills": [
+                {
+                    "name": "kept",
+                    "path": skill_dir,
+                    "source_type": "self",
+                    "installedAt": "2024-01-01T00:00:00Z"
+                },
+                {"name": "gone", "path": temp_dir.path().join("gone"), "source_type": "self"},
+            ]
+        });
+        fs::write(&lock_path, legacy.to_string()).unwrap();
+
+        let manager = LockManager::new(lock_path.clone());
+        let lock = manager.load().unwrap();
+        let kept = &lock.skills["kept"];
+        assert_eq!(kept.skill_folder_hash, "");
+        assert_eq!(kept.installed_at.to_rfc3339(), "2024-01-01T00:00:00+00:00");
+        assert_eq!(kept.updated_at, kept.installed_at);
+        assert_eq!(lock.skills["gone"].skill_folder_hash, "");
+
+        // Reading, including a dry-run doctor, leaves the file as it was
+        assert_eq!(&manager.load().unwrap().skills["kept"], kept);
+        assert_eq!(
+            manager.doctor(true).unwrap().remaining[0],
+            LockIssue::LegacyFormat
+        );
+        assert_eq!(fs::read_to_string(&lock_path).unwrap(), legacy.to_string());
+
+        // Writing persists the migration, so loading again is a plain read
+        let report = manager.doctor(false).unwrap();
+        assert_eq!(report.fixed[0], LockIssue::LegacyFormat);
+        let migrated = fs::read_to_string(&lock_path).unwrap();
+        assert!(!SkillLock::is_legacy(
+            &serde_json::from_str(&migrated).unwrap()
+        ));
+        assert_eq!(
+            manager.load().unwrap().skills["kept"].skill_folder_hash,
+            compute_skill_hash(&skill_dir).unwrap()
+        );
+        assert_eq!(manager.doctor(true).unwrap(), DoctorReport::default());
+    }
+
+    #[test]
+    fn test_save_fills_missing_hashes() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+
+        let legacy = serde_json::json!({
+            "skills": [
+                {"name": "kept", "path": skill_dir, "source_type": "self"},
+                {"name": "gone", "path": temp_dir.path().join("gone"), "source_type": "self"},
+            ]
+        });
+        fs::write(&lock_path, legacy.to_string()).unwrap();
+
+        let manager = LockManager::new(lock_path);
+        let lock = manager.load().unwrap();
+        manager.save(&lock).unwrap();
+        let saved = manager.load().unwrap();
+        assert_eq!(
+            saved.skills["kept"].skill_folder_hash,
+            compute_skill_hash(&skill_dir).unwrap()
+        );
+        assert_eq!(saved.skills["kept"].hash_version, HASH_VERSION);
+        assert_eq!(saved.skills["gone"].skill_folder_hash, "");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_unreadable_skill_dir_is_missing_path() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+        let legacy = serde_json::json!({
+            "skills": [{"name": "locked", "path": skill_dir, "source_type": "self"}]
+        });
+        fs::write(&lock_path, legacy.to_string()).unwrap();
+
+        fs::set_permissions(&skill_dir, fs::Permissions::from_mode(0o000)).unwrap();
+        let unreadable = fs::read_dir(&skill_dir).is_err();
+        let manager = LockManager::new(lock_path);
+        let lock = manager.load();
+        let report = manager.doctor(true);
+        fs::set_permissions(&skill_dir, fs::Permissions::from_mode(0o755)).unwrap();
+
+        // Loading doesn't touch the skill directory; root can read it regardless
+        assert!(lock.unwrap().skills.contains_key("locked"));
+        if unreadable {
+            assert!(report.unwrap().remaining.contains(&LockIssue::MissingPath {
+                skill: "locked".to_string(),
+                path: skill_dir.display().to_string(),
+            }));
+        }
+    }
+
+    #[test]
+    fn test_skills_cli_lock_kept_in_its_format() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let skill_dir = temp_dir.path().join("skill");
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), "test content").unwrap();
+        let upstream = serde_json::json!({
+            "version": 3,
+            "skills": {
+                "pdf": {
+                    "source": "vercel-labs/agent-skills",
+                    "sourceType": "github",
+                    "sourceUrl": "https://github.com/vercel-labs/agent-skills.git",
+                    "skillPath": "skills/pdf/SKILL.md",
+                    "skillFolderHash": "4b825dc642cb6eb9a060e54bf8d69288fbee4904",
+                    "installedAt": "2025-01-01T00:00:00Z",
+                    "updatedAt": "2025-01-01T00:00:00Z"
+                }
+            },
+            "dismissed": { "findSkillsPrompt": true }
+        });
+        fs::write(&lock_path, upstream.to_string()).unwrap();
+        let source = Source {
+            source_type: SourceType::Self_,
+            url: None,
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+
+        let manager = LockManager::new(lock_path.clone());
+        assert_eq!(
+            manager.get_entry("pdf").unwrap().unwrap().source,
+            "vercel-labs/agent-skills"
+        );
+        manager.update_entry("docx", &source, &skill_dir).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&lock_path).unwrap()).unwrap();
+        assert_eq!(written["version"], 3);
+        assert_eq!(written["dismissed"], upstream["dismissed"]);
+        assert_eq!(written["skills"]["pdf"]["skillPath"], "skills/pdf/SKILL.md");
+        assert_eq!(written["skills"]["docx"]["sourceType"], "self");
+        // Each tool's hash stays in its own field
+        assert_eq!(
+            written["skills"]["pdf"]["skillFolderHash"],
+            upstream["skills"]["pdf"]["skillFolderHash"]
+        );
+        assert_eq!(written["skills"]["docx"]["skillFolderHash"], "");
+        let docx_hash = compute_skill_hash(&skill_dir).unwrap();
+        assert_eq!(written["skills"]["docx"]["contentHash"], docx_hash);
+        assert_eq!(
+            manager.load().unwrap().skills["docx"].skill_folder_hash,
+            docx_hash
+        );
+
+        let manager = manager.with_format(LockFormat::Native);
+        manager.update_entry("docx", &source, &skill_dir).unwrap();
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&lock_path).unwrap()).unwrap();
+        assert_eq!(written["version"], LOCK_VERSION);
+        assert_eq!(written["skills"]["pdf"]["relativePath"], "skills/pdf");
+    }
+
     #[test]
     fn test_compute_skill_hash() {
         let temp_dir = TempDir::new().unwrap();
@@ -222,6 +1161,40 @@ mod tests {
         assert_ne!(hash1, hash3);
     }
 
+    #[test]
+    fn test_skill_hash_covers_auxiliary_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut skill = Skill {
+            name: "aux".to_string(),
+            description: "Skill with scripts".to_string(),
+            path: None,
+            raw_content: "---\nname: aux\n---\n".to_string(),
+            metadata: Default::default(),
+            auxiliary_files: Default::default(),
+        };
+        skill
+            .auxiliary_files
+            .insert("scripts/run.sh".to_string(), "echo one".to_string());
+        skill
+            .auxiliary_files
+            .insert("./notes.md".to_string(), "notes".to_string());
+        let config = InstallConfig::new(temp_dir.path().to_path_buf());
+        let result = crate::installer::install_skill(&skill, &config).unwrap();
+
+        let installed = compute_skill_hash(&result.path).unwrap();
+        assert_eq!(installed, compute_content_hash(&skill));
+
+        fs::write(result.path.join("scripts/run.sh"), "echo two").unwrap();
+        assert_ne!(compute_skill_hash(&result.path).unwrap(), installed);
+        skill
+            .auxiliary_files
+            .insert("scripts/run.sh".to_string(), "echo two".to_string());
+        assert_eq!(
+            compute_skill_hash(&result.path).unwrap(),
+            compute_content_hash(&skill)
+        );
+    }
+
     #[test]
     fn test_embedded_source_lock_entry() {
         let temp_dir = TempDir::new().unwrap();
@@ -271,6 +1244,84 @@ mod tests {
         assert!(lock.skills.is_empty());
     }
 
+    #[test]
+    fn test_load_corrupt_lock_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        fs::write(&lock_path, "{ not json").unwrap();
+
+        let manager = LockManager::new(lock_path.clone());
+        let err = manager.load().unwrap_err();
+        assert!(matches!(err, SkillsError::LockCorrupt { .. }));
+        assert_eq!(err.code(), "lock_corrupt");
+        assert_eq!(err.path(), Some(lock_path.as_path()));
+    }
+
+    #[test]
+    fn test_legacy_entry_with_invalid_name_is_not_migrated() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let legacy = serde_json::json!({
+            "skills": [
+                {"name": "My Skill", "path": temp_dir.path(), "source_type": "self"},
+                {"name": "kept", "path": temp_dir.path(), "source_type": "self"},
+            ]
+        });
+        fs::write(&lock_path, legacy.to_string()).unwrap();
+
+        // The entry is skipped rather than blocking every other skill
+        let manager = LockManager::new(lock_path.clone());
+        let lock = manager.load().unwrap();
+        assert_eq!(lock.skills.keys().collect::<Vec<_>>(), vec!["kept"]);
+
+        // and reported, then dropped, by doctor
+        let report = manager.doctor(false).unwrap();
+        assert!(report.fixed.contains(&LockIssue::LegacyFormat));
+        assert!(report.fixed.iter().any(|issue| matches!(
+            issue,
+            LockIssue::InvalidField { field, .. } if field == "skills[0].name"
+        )));
+        assert_eq!(
+            manager.load().unwrap().skills.keys().collect::<Vec<_>>(),
+            vec!["kept"]
+        );
+    }
+
+    #[test]
+    fn test_entry_with_invalid_name_is_skipped_on_load() {
+        let temp_dir = TempDir::new().unwrap();
+        let lock_path = temp_dir.path().join(".skill-lock.json");
+        let source = Source {
+            source_type: SourceType::Self_,
+            url: None,
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+        let manager = LockManager::new(lock_path.clone());
+        manager
+            .update_entry("kept", &source, temp_dir.path())
+            .unwrap();
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&lock_path).unwrap()).unwrap();
+        value["skills"]["../escape"] = value["skills"]["kept"].clone();
+        fs::write(&lock_path, value.to_string()).unwrap();
+
+        let lock = manager.load().unwrap();
+        assert_eq!(lock.skills.keys().collect::<Vec<_>>(), vec!["kept"]);
+        let report = manager.doctor(true).unwrap();
+        assert_eq!(report.remaining.len(), 1);
+        assert!(matches!(
+            &report.remaining[0],
+            LockIssue::InvalidField { field, .. } if field == "skills.../escape"
+        ));
+
+        // Other violations still fail strict loading
+        value["skills"]["kept"]["sourceType"] = 1.into();
+        fs::write(&lock_path, value.to_string()).unwrap();
+        assert_eq!(manager.load().unwrap_err().code(), "lock_invalid");
+    }
+
     #[test]
     fn test_load_legacy_array_format() {
         // Test loading vercel-lab/AgentSkills format
@@ -322,4 +1373,300 @@ mod tests {
         assert_eq!(reloaded.version, "1.0");
         assert_eq!(reloaded.skills.len(), 2);
     }
+
+    fn write_local_skill(repo: &Path, name: &str, body: &str) {
+        let dir = repo.join("skills").join(name);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(
+            dir.join("SKILL.md"),
+            format!("---\nname: {}\ndescription: {}\n---\n{}", name, name, body),
+        )
+        .unwrap();
+    }
+
+    fn local_source(repo: &Path) -> Source {
+        Source {
+            source_type: SourceType::Local,
+            url: Some(repo.display().to_string()),
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        }
+    }
+
+    #[test]
+    fn test_install_frozen_reinstalls_locked_skills() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_local_skill(&repo, "pdf", "v1");
+
+        // Record an install, then lose the installed files
+        let config = InstallConfig::new(temp_dir.path().join(".agents/skills"));
+        let manager = LockManager::new(temp_dir.path().join(".agents/.skill-lock.json"));
+        let skill = discover_skills_with_provider(&local_source(&repo), &Default::default(), None)
+            .unwrap()
+            .remove(0);
+        let installed = crate::installer::install_skill(&skill, &config).unwrap();
+        let hash = compute_skill_hash(&installed.path).unwrap();
+        manager
+            .update_skill_entry(&skill, &local_source(&repo), &installed.path, hash, None)
+            .unwrap();
+        fs::remove_dir_all(&installed.path).unwrap();
+
+        let lock = manager.load().unwrap();
+        assert_eq!(
+            lock.skills["pdf"].relative_path.as_deref(),
+            Some("skills/pdf")
+        );
+        let results = install_frozen(&lock, &config, &Default::default(), None).unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].0.name, "pdf");
+        assert_eq!(
+            compute_skill_hash(&installed.path).unwrap(),
+            lock.skills["pdf"].skill_folder_hash
+        );
+        assert_eq!(manager.load().unwrap(), lock);
+    }
+
+    #[test]
+    fn test_update_skill_entry_records_subpath_and_relative_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_local_skill(&repo.join("pack"), "pdf", "v1");
+        write_local_skill(&repo, "docx", "v1");
+
+        let source = Source {
+            subpath: Some("pack".to_string()),
+            ..local_source(&repo)
+        };
+        let skills = discover_skills_with_provider(&source, &Default::default(), None).unwrap();
+        assert_eq!(skills.len(), 1);
+
+        let manager = LockManager::new(temp_dir.path().join(".skill-lock.json"));
+        manager
+            .update_skill_entry(&skills[0], &source, &repo, "hash".to_string(), None)
+            .unwrap();
+        let entry = manager.get_entry("pdf").unwrap().unwrap();
+        assert_eq!(entry.subpath.as_deref(), Some("pack"));
+        assert_eq!(entry.relative_path.as_deref(), Some("pack/skills/pdf"));
+        assert_eq!(
+            entry_source(&entry).unwrap().subpath.as_deref(),
+            Some("pack")
+        );
+
+        let json = fs::read_to_string(temp_dir.path().join(".skill-lock.json")).unwrap();
+        assert!(json.contains("\"relativePath\": \"pack/skills/pdf\""));
+    }
+
+    #[test]
+    fn test_reinstall_from_other_source_rewrites_provenance() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_local_skill(&repo, "pdf", "v1");
+        let skill = discover_skills_with_provider(&local_source(&repo), &Default::default(), None)
+            .unwrap()
+            .remove(0);
+
+        let manager = LockManager::new(temp_dir.path().join(".skill-lock.json"));
+        let previous = manager
+            .update_skill_entry(&skill, &local_source(&repo), &repo, "h1".to_string(), None)
+            .unwrap();
+        assert_eq!(previous, None);
+        // Reinstalling from the same origin isn't a change
+        let previous = manager
+            .update_skill_entry(&skill, &local_source(&repo), &repo, "h2".to_string(), None)
+            .unwrap();
+        assert_eq!(previous, None);
+
+        let github = Source {
+            source_type: SourceType::Github,
+            url: Some("https://github.com/example/skills".to_string()),
+            subpath: None,
+            skill_filter: None,
+            ref_: Some("main".to_string()),
+        };
+        let previous = manager
+            .update_skill_entry(&skill, &github, &repo, "h3".to_string(), None)
+            .unwrap()
+            .unwrap();
+        assert_eq!(previous.source_type, "local");
+        assert_eq!(previous.source_url, Some(repo.display().to_string()));
+
+        let entry = manager.get_entry("pdf").unwrap().unwrap();
+        assert_eq!(entry.source, "Github");
+        assert_eq!(entry.source_type, "github");
+        assert_eq!(entry.source_url, github.url);
+        assert_eq!(entry.ref_.as_deref(), Some("main"));
+        assert_eq!(entry.history, vec![previous]);
+    }
+
+    #[test]
+    fn test_find_lock_conflicts_across_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_local_skill(&repo, "pdf", "v1");
+        let skills =
+            discover_skills_with_provider(&local_source(&repo), &Default::default(), None).unwrap();
+
+        let manager = LockManager::new(temp_dir.path().join(".skill-lock.json"));
+        let installed = temp_dir.path().join(".agents/skills/pdf");
+        manager
+            .update_skill_entry(
+                &skills[0],
+                &local_source(&repo),
+                &installed,
+                "h".to_string(),
+                None,
+            )
+            .unwrap();
+        let lock = manager.load().unwrap();
+
+        // Same origin, even at another ref, is an update rather than a conflict
+        let mut same = local_source(&repo);
+        same.ref_ = Some("v2".to_string());
+        assert!(find_lock_conflicts(&lock, &skills, &same).is_empty());
+
+        let other = local_source(&temp_dir.path().join("other"));
+        assert_eq!(
+            find_lock_conflicts(&lock, &skills, &other),
+            vec![DuplicateSkill {
+                name: "pdf".to_string(),
+                kept: Some(installed.display().to_string()),
+                duplicate: skills[0].path.clone(),
+                renamed_to: None,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_install_frozen_fails_on_hash_mismatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_local_skill(&repo, "pdf", "v1");
+
+        let config = InstallConfig::new(temp_dir.path().join(".agents/skills"));
+        let manager = LockManager::new(temp_dir.path().join(".agents/.skill-lock.json"));
+        let skill = discover_skills_with_provider(&local_source(&repo), &Default::default(), None)
+            .unwrap()
+            .remove(0);
+        let installed = crate::installer::install_skill(&skill, &config).unwrap();
+        manager
+            .update_entry("pdf", &local_source(&repo), &installed.path)
+            .unwrap();
+
+        // The source moved on since the lock was written
+        write_local_skill(&repo, "pdf", "v2");
+        let lock = manager.load().unwrap();
+        let err = install_frozen(&lock, &config, &Default::default(), None).unwrap_err();
+        assert_eq!(err.code(), "lock_mismatch");
+        assert!(err.to_string().contains("skillFolderHash"));
+        // Nothing was overwritten
+        assert!(fs::read_to_string(installed.path.join("SKILL.md"))
+            .unwrap()
+            .ends_with("v1"));
+    }
+
+    #[test]
+    fn test_unversioned_hash_verifies_until_doctor_upgrades_it() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_local_skill(&repo, "pdf", "v1");
+        fs::write(repo.join("skills/pdf/notes.md"), "notes").unwrap();
+
+        let config = InstallConfig::new(temp_dir.path().join(".agents/skills"));
+        let lock_path = temp_dir.path().join(".agents/.skill-lock.json");
+        let manager = LockManager::new(lock_path.clone());
+        let skill = discover_skills_with_provider(&local_source(&repo), &Default::default(), None)
+            .unwrap()
+            .remove(0);
+        let installed = crate::installer::install_skill(&skill, &config).unwrap();
+        manager
+            .update_entry("pdf", &local_source(&repo), &installed.path)
+            .unwrap();
+
+        // An entry written before the hash was versioned only hashed SKILL.md
+        let mut value: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&lock_path).unwrap()).unwrap();
+        let entry = value["skills"]["pdf"].as_object_mut().unwrap();
+        entry.remove("hashVersion");
+        entry["skillFolderHash"] = format!("{:x}", Sha256::digest(&skill.raw_content)).into();
+        fs::write(&lock_path, value.to_string()).unwrap();
+
+        let lock = manager.load().unwrap();
+        assert_eq!(lock.skills["pdf"].hash_version, 1);
+        install_frozen(&lock, &config, &Default::default(), None).unwrap();
+
+        let report = manager.doctor(false).unwrap();
+        assert_eq!(
+            report.fixed,
+            vec![LockIssue::OutdatedHash {
+                skill: "pdf".to_string(),
+                hash_version: 1,
+            }]
+        );
+        let entry = &manager.load().unwrap().skills["pdf"];
+        assert_eq!(entry.hash_version, HASH_VERSION);
+        assert_eq!(entry.skill_folder_hash, compute_content_hash(&skill));
+    }
+
+    #[test]
+    fn test_install_frozen_checks_provider_commit() {
+        use crate::providers::MockProvider;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config = InstallConfig::new(temp_dir.path().join(".agents/skills"));
+        let manager = LockManager::new(temp_dir.path().join(".agents/.skill-lock.json"));
+        let source = Source {
+            source_type: SourceType::Github,
+            url: Some("https://github.com/example/skills".to_string()),
+            subpath: None,
+            skill_filter: None,
+            ref_: Some("v1".to_string()),
+        };
+        let provider = MockProvider::new(provider_skills())
+            .with_hash("hash-1".to_string())
+            .with_commit("abc123".to_string());
+        manager
+            .update_skill_entry(
+                &provider_skills()[0],
+                &source,
+                &temp_dir.path().join(".agents/skills/pdf"),
+                "hash-1".to_string(),
+                Some(&provider),
+            )
+            .unwrap();
+        let lock = manager.load().unwrap();
+        assert_eq!(lock.skills["pdf"].ref_.as_deref(), Some("v1"));
+        // The ref is recorded together with the commit it resolved to at install time
+        assert_eq!(lock.skills["pdf"].commit.as_deref(), Some("abc123"));
+
+        install_frozen(&lock, &config, &Default::default(), Some(&provider)).unwrap();
+
+        let moved = MockProvider::new(vec![])
+            .with_hash("hash-1".to_string())
+            .with_commit("def456".to_string());
+        let err = install_frozen(&lock, &config, &Default::default(), Some(&moved)).unwrap_err();
+        assert_eq!(err.code(), "skill_not_found");
+
+        let provider = MockProvider::new(provider_skills())
+            .with_hash("hash-1".to_string())
+            .with_commit("def456".to_string());
+        let err = install_frozen(&lock, &config, &Default::default(), Some(&provider)).unwrap_err();
+        assert_eq!(err.code(), "lock_mismatch");
+        assert!(err.to_string().contains("commit"));
+
+        let err = install_frozen(&lock, &config, &Default::default(), None).unwrap_err();
+        assert_eq!(err.code(), "invalid_source");
+    }
+
+    fn provider_skills() -> Vec<Skill> {
+        vec![Skill {
+            name: "pdf".to_string(),
+            description: "pdf".to_string(),
+            path: None,
+            raw_content: "---\nname: pdf\ndescription: pdf\n---\n".to_string(),
+            metadata: Default::default(),
+            auxiliary_files: Default::default(),
+        }]
+    }
 }
@@ -0,0 +1,430 @@
+use crate::discovery::{
+    discover_skills_with_report, find_duplicate_skills, DiscoveryConfig, InvalidSkill,
+};
+use crate::error::{Result, SkillsError};
+use crate::lock::{compute_versioned_content_hash, lock_source_type};
+use crate::providers::SkillProvider;
+use crate::types::{Skill, SkillFilter, SkillLock, Source, SourceType, HASH_VERSION};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Default manifest location, relative to the project root
+pub const MANIFEST_PATH: &str = ".agents/skills.toml";
+
+/// Declarative list of the skills a project wants installed.
+///
+/// ```toml
+/// agents = ["claude"]
+///
+/// [[sources]]
+/// type = "local"
+/// url = "../shared-skills"
+/// skills = ["pdf-*", "docx"]
+/// exclude = ["pdf-old"]
+/// ```
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    /// Agents every source is linked into unless the source overrides it
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub agents: Vec<String>,
+    #[serde(default)]
+    pub sources: Vec<ManifestSource>,
+}
+
+/// A source declared in the manifest
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ManifestSource {
+    #[serde(rename = "type")]
+    pub source_type: SourceType,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subpath: Option<String>,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub ref_: Option<String>,
+    /// Skill names or glob patterns to install (all skills when empty)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skills: Vec<String>,
+    /// Skill names or glob patterns to leave out
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+    /// Agents for this source, replacing the manifest-wide list
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agents: Option<Vec<String>>,
+}
+
+impl ManifestSource {
+    /// Build the [`Source`] used for discovery
+    pub fn to_source(&self) -> Source {
+        let filter = SkillFilter::new(&self.skills, &self.exclude);
+        Source {
+            source_type: self.source_type.clone(),
+            url: self.url.clone(),
+            subpath: self.subpath.clone(),
+            skill_filter: (!filter.is_empty()).then_some(filter),
+            ref_: self.ref_.clone(),
+        }
+    }
+
+    /// Agents this source's skills are linked into
+    pub fn agents<'a>(&'a self, manifest: &'a Manifest) -> &'a [String] {
+        self.agents.as_deref().unwrap_or(&manifest.agents)
+    }
+}
+
+impl Manifest {
+    /// Load a manifest from a TOML file
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).map_err(SkillsError::io("read manifest", path))?;
+        Self::parse(&content).map_err(|e| match e {
+            SkillsError::InvalidManifest { path: None, reason } => SkillsError::InvalidManifest {
+                path: Some(path.to_path_buf()),
+                reason,
+            },
+            other => other,
+        })
+    }
+
+    /// Parse a manifest from TOML
+    pub fn parse(content: &str) -> Result<Self> {
+        let manifest: Manifest =
+            toml::from_str(content).map_err(|e| SkillsError::InvalidManifest {
+                path: None,
+                reason: e.message().to_string(),
+            })?;
+        for source in &manifest.sources {
+            if source.url.is_none() && !source.source_type.is_embedded() {
+                return Err(SkillsError::InvalidManifest {
+                    path: None,
+                    reason: format!("source of type {:?} needs a url", source.source_type),
+                });
+            }
+        }
+        Ok(manifest)
+    }
+}
+
+/// A skill the manifest resolves to
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedSkill {
+    pub skill: Skill,
+    pub source: Source,
+    pub agents: Vec<String>,
+}
+
+/// Changes needed to make the installed skills match a manifest
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncPlan {
+    /// Declared skills that aren't installed
+    pub install: Vec<PlannedSkill>,
+    /// Declared skills whose content or origin differs from the lock
+    pub update: Vec<PlannedSkill>,
+    /// Declared skills that are installed as declared
+    pub unchanged: Vec<PlannedSkill>,
+    /// Skills in the lock that the manifest no longer declares
+    pub remove: Vec<String>,
+    /// SKILL.md files in the sources that were skipped because they don't parse or validate
+    pub invalid: Vec<InvalidSkill>,
+}
+
+impl SyncPlan {
+    /// Whether applying the plan would change nothing
+    pub fn is_empty(&self) -> bool {
+        self.install.is_empty() && self.update.is_empty() && self.remove.is_empty()
+    }
+}
+
+/// Compare the skills a manifest declares with the lock.
+///
+/// A skill counts as installed when the lock has an entry whose directory still
+/// exists; it is up to date when the entry's hash, source type and URL match.
+/// Two sources resolving to the same skill name is an error.
+pub fn plan_sync(
+    manifest: &Manifest,
+    lock: &SkillLock,
+    config: &DiscoveryConfig,
+    provider: Option<&dyn SkillProvider>,
+) -> Result<SyncPlan> {
+    let mut declared = Vec::new();
+    let mut invalid = Vec::new();
+    for manifest_source in &manifest.sources {
+        let source = manifest_source.to_source();
+        if source.source_type.requires_provider() && provider.is_none() {
+            return Err(SkillsError::InvalidSource {
+                reason: format!(
+                    "No provider available for {:?} source {}",
+                    source.source_type,
+                    source.url.as_deref().unwrap_or_default()
+                ),
+            });
+        }
+
+        let report = discover_skills_with_report(&source, config, provider)?;
+        invalid.extend(report.invalid);
+        for skill in report.skills {
+            declared.push(PlannedSkill {
+                skill,
+                source: source.clone(),
+                agents: manifest_source.agents(manifest).to_vec(),
+            });
+        }
+    }
+
+    let skills: Vec<Skill> = declared.iter().map(|p| p.skill.clone()).collect();
+    if let Some(duplicate) = find_duplicate_skills(&skills).into_iter().next() {
+        return Err(SkillsError::DuplicateSkill {
+            name: duplicate.name,
+            first: duplicate.kept.map(PathBuf::from),
+            second: duplicate.duplicate.map(PathBuf::from),
+        });
+    }
+
+    let mut plan = SyncPlan {
+        invalid,
+        ..SyncPlan::default()
+    };
+    for planned in declared {
+        let expected_hash = match provider {
+            Some(provider) if planned.source.source_type.requires_provider() => provider
+                .get_folder_hash(&planned.skill)
+                .map_err(|error| SkillsError::Provider {
+                    context: format!("Failed to hash skill {:?}", planned.skill.name),
+                    error,
+                })?,
+            _ => {
+                let hash_version = lock
+                    .skills
+                    .get(&planned.skill.name)
+                    .map_or(HASH_VERSION, |entry| entry.hash_version);
+                compute_versioned_content_hash(&planned.skill, hash_version)
+            }
+        };
+
+        match lock.skills.get(&planned.skill.name) {
+            Some(entry) if Path::new(&entry.skill_path).exists() => {
+                let up_to_date = entry.skill_folder_hash == expected_hash
+                    && entry.source_type == lock_source_type(&planned.source)
+                    && entry.source_url == planned.source.url
+                    && entry.ref_ == planned.source.ref_
+                    && entry.subpath == planned.source.subpath;
+                if up_to_date {
+                    plan.unchanged.push(planned);
+                } else {
+                    plan.update.push(planned);
+                }
+            }
+            _ => plan.install.push(planned),
+        }
+    }
+
+    let declared_names: Vec<&str> = plan
+        .install
+        .iter()
+        .chain(&plan.update)
+        .chain(&plan.unchanged)
+        .map(|p| p.skill.name.as_str())
+        .collect();
+    plan.remove = lock
+        .skills
+        .keys()
+        .filter(|name| !declared_names.contains(&name.as_str()))
+        .cloned()
+        .collect();
+    plan.remove.sort();
+
+    Ok(plan)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::discovery::discover_skills_with_provider;
+    use crate::lock::compute_content_hash;
+    use crate::types::LockEntry;
+    use tempfile::TempDir;
+
+    fn write_skill(dir: &Path, name: &str) {
+        fs::create_dir_all(dir).unwrap();
+        fs::write(
+            dir.join("SKILL.md"),
+            format!("---\nname: {}\ndescription: {}\n---\n", name, name),
+        )
+        .unwrap();
+    }
+
+    fn lock_entry(path: &Path, source_url: &str, hash: String) -> LockEntry {
+        let now = chrono::Utc::now();
+        LockEntry {
+            source: "Local".to_string(),
+            source_type: "local".to_string(),
+            source_url: Some(source_url.to_string()),
+            skill_path: path.display().to_string(),
+            skill_folder_hash: hash,
+            hash_version: HASH_VERSION,
+            installed_at: now,
+            updated_at: now,
+            ref_: None,
+            commit: None,
+            subpath: None,
+            relative_path: None,
+            history: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let manifest = Manifest::parse(
+            r#"
+agents = ["claude"]
+
+[[sources]]
+type = "self"
+
+[[sources]]
+type = "github"
+url = "https://github.com/example/skills"
+ref = "v1.2.0"
+skills = ["pdf-*", "docx"]
+exclude = ["pdf-old"]
+agents = ["opencode"]
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(manifest.agents, vec!["claude"]);
+        assert_eq!(manifest.sources.len(), 2);
+        assert_eq!(manifest.sources[0].agents(&manifest), ["claude"]);
+
+        let github = &manifest.sources[1];
+        assert_eq!(github.agents(&manifest), ["opencode"]);
+        let source = github.to_source();
+        assert_eq!(source.source_type, SourceType::Github);
+        assert_eq!(source.ref_.as_deref(), Some("v1.2.0"));
+        let filter = source.skill_filter.unwrap();
+        assert!(filter.matches("pdf-tools"));
+        assert!(!filter.matches("pdf-old"));
+    }
+
+    #[test]
+    fn test_parse_manifest_errors() {
+        let err = Manifest::parse("[[sources]]\ntype = \"github\"\n").unwrap_err();
+        assert_eq!(err.code(), "invalid_manifest");
+        assert!(err.to_string().contains("needs a url"));
+
+        let err = Manifest::parse("[[sources]]\ntype = \"self\"\ncolour = 1\n").unwrap_err();
+        assert_eq!(err.code(), "invalid_manifest");
+
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("skills.toml");
+        fs::write(&path, "sources = 1").unwrap();
+        let err = Manifest::load(&path).unwrap_err();
+        assert_eq!(err.path(), Some(path.as_path()));
+    }
+
+    #[test]
+    fn test_plan_sync() {
+        let temp_dir = TempDir::new().unwrap();
+        let repo = temp_dir.path().join("repo");
+        write_skill(&repo.join("skills/fresh"), "fresh");
+        write_skill(&repo.join("skills/same"), "same");
+        write_skill(&repo.join("skills/changed"), "changed");
+        write_skill(&repo.join("skills/broken"), "Not Valid");
+        let repo_url = repo.display().to_string();
+
+        let installed = temp_dir.path().join("installed");
+        fs::create_dir_all(installed.join("same")).unwrap();
+        fs::create_dir_all(installed.join("changed")).unwrap();
+        fs::create_dir_all(installed.join("stale")).unwrap();
+
+        let manifest = Manifest {
+            agents: vec!["claude".to_string()],
+            sources: vec![ManifestSource {
+                source_type: SourceType::Local,
+                url: Some(repo_url.clone()),
+                subpath: None,
+                ref_: None,
+                skills: Vec::new(),
+                exclude: Vec::new(),
+                agents: None,
+            }],
+        };
+
+        let discovered = discover_skills_with_provider(
+            &manifest.sources[0].to_source(),
+            &Default::default(),
+            None,
+        )
+        .unwrap();
+        let same_hash = compute_content_hash(discovered.iter().find(|s| s.name == "same").unwrap());
+
+        let mut lock = SkillLock::new();
+        lock.skills.insert(
+            "same".to_string(),
+            lock_entry(&installed.join("same"), &repo_url, same_hash),
+        );
+        lock.skills.insert(
+            "changed".to_string(),
+            lock_entry(&installed.join("changed"), &repo_url, "old".to_string()),
+        );
+        lock.skills.insert(
+            "stale".to_string(),
+            lock_entry(&installed.join("stale"), &repo_url, "old".to_string()),
+        );
+
+        let plan = plan_sync(&manifest, &lock, &DiscoveryConfig::default(), None).unwrap();
+        let names = |planned: &[PlannedSkill]| -> Vec<String> {
+            planned.iter().map(|p| p.skill.name.clone()).collect()
+        };
+        assert_eq!(names(&plan.install), vec!["fresh"]);
+        assert_eq!(names(&plan.update), vec!["changed"]);
+        assert_eq!(names(&plan.unchanged), vec!["same"]);
+        assert_eq!(plan.remove, vec!["stale"]);
+        assert_eq!(plan.install[0].agents, vec!["claude"]);
+        assert_eq!(plan.invalid.len(), 1);
+        assert!(plan.invalid[0].path.ends_with("broken/SKILL.md"));
+        assert!(!plan.is_empty());
+    }
+
+    #[test]
+    fn test_plan_sync_requires_provider_for_remote_sources() {
+        let manifest = Manifest::parse(
+            "[[sources]]\ntype = \"github\"\nurl = \"https://github.com/example/skills\"\n",
+        )
+        .unwrap();
+        let err = plan_sync(
+            &manifest,
+            &SkillLock::new(),
+            &DiscoveryConfig::default(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "invalid_source");
+    }
+
+    #[test]
+    fn test_plan_sync_rejects_same_skill_from_two_sources() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first");
+        let second = temp_dir.path().join("second");
+        write_skill(&first.join("skills/pdf"), "pdf");
+        write_skill(&second.join("skills/pdf"), "pdf");
+
+        let manifest = Manifest::parse(&format!(
+            "[[sources]]\ntype = \"local\"\nurl = {:?}\n\n[[sources]]\ntype = \"local\"\nurl = {:?}\n",
+            first.display().to_string(),
+            second.display().to_string()
+        ))
+        .unwrap();
+        let err = plan_sync(
+            &manifest,
+            &SkillLock::new(),
+            &DiscoveryConfig::default(),
+            None,
+        )
+        .unwrap_err();
+        assert_eq!(err.code(), "duplicate_skill");
+    }
+}
@@ -0,0 +1,139 @@
+use crate::error::{Result, SkillsError};
+use directories::BaseDirs;
+use std::path::PathBuf;
+
+/// Environment variable overriding the home directory global installs go to
+pub const HOME_ENV: &str = "AGENT_SKILLS_HOME";
+
+/// Per-user locations used by global installs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Paths {
+    /// Directory whose `.agents` holds globally installed skills
+    pub home: PathBuf,
+    /// Configuration directory agents such as opencode read their skills from
+    pub config_dir: PathBuf,
+}
+
+impl Paths {
+    /// Paths rooted at `home`, with the configuration directory at `home/.config`
+    pub fn new(home: impl Into<PathBuf>) -> Self {
+        let home = home.into();
+        Self {
+            config_dir: home.join(".config"),
+            home,
+        }
+    }
+
+    /// Resolve the paths from an explicit home (e.g. `--home`), then `AGENT_SKILLS_HOME`,
+    /// then the user's home directory.
+    ///
+    /// `XDG_CONFIG_HOME` is honoured only for the user's own home directory, so an
+    /// overridden home keeps everything underneath it.
+    pub fn resolve(home: Option<PathBuf>) -> Result<Self> {
+        let home = home.or_else(|| env_path(HOME_ENV));
+        resolve_with(home, env_path("XDG_CONFIG_HOME"), || {
+            BaseDirs::new().map(|dirs| dirs.home_dir().to_path_buf())
+        })
+    }
+
+    pub fn with_config_dir(mut self, config_dir: impl Into<PathBuf>) -> Self {
+        self.config_dir = config_dir.into();
+        self
+    }
+
+    /// Directory skills are installed into globally
+    pub fn canonical_dir(&self) -> PathBuf {
+        self.home.join(".agents/skills")
+    }
+
+    /// Lock file of the global installs
+    pub fn lock_path(&self) -> PathBuf {
+        self.home.join(".agents/.skill-lock.json")
+    }
+
+    /// Directory opencode loads global skills from
+    pub fn opencode_skills_dir(&self) -> PathBuf {
+        self.config_dir.join("opencode/skills")
+    }
+}
+
+fn resolve_with(
+    home: Option<PathBuf>,
+    xdg_config_home: Option<PathBuf>,
+    user_home: impl FnOnce() -> Option<PathBuf>,
+) -> Result<Paths> {
+    if let Some(home) = home {
+        return Ok(Paths::new(home));
+    }
+    let paths = Paths::new(user_home().ok_or_else(|| {
+        SkillsError::io("determine home directory", "~")(std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            format!("no home directory for the current user; set {}", HOME_ENV),
+        ))
+    })?);
+    // The XDG spec says relative values are invalid and must be ignored
+    match xdg_config_home.filter(|dir| dir.is_absolute()) {
+        Some(config_dir) => Ok(paths.with_config_dir(config_dir)),
+        None => Ok(paths),
+    }
+}
+
+/// Non-empty path from an environment variable
+fn env_path(name: &str) -> Option<PathBuf> {
+    std::env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn user_home() -> Option<PathBuf> {
+        Some(PathBuf::from("/home/u"))
+    }
+
+    #[test]
+    fn test_default_paths() {
+        let paths = resolve_with(None, None, user_home).unwrap();
+        assert_eq!(paths.home, PathBuf::from("/home/u"));
+        assert_eq!(
+            paths.canonical_dir(),
+            PathBuf::from("/home/u/.agents/skills")
+        );
+        assert_eq!(
+            paths.lock_path(),
+            PathBuf::from("/home/u/.agents/.skill-lock.json")
+        );
+        assert_eq!(
+            paths.opencode_skills_dir(),
+            PathBuf::from("/home/u/.config/opencode/skills")
+        );
+    }
+
+    #[test]
+    fn test_xdg_config_home() {
+        let paths = resolve_with(None, Some("/xdg".into()), user_home).unwrap();
+        assert_eq!(
+            paths.opencode_skills_dir(),
+            PathBuf::from("/xdg/opencode/skills")
+        );
+
+        let paths = resolve_with(None, Some("relative".into()), user_home).unwrap();
+        assert_eq!(paths.config_dir, PathBuf::from("/home/u/.config"));
+    }
+
+    #[test]
+    fn test_home_override_keeps_everything_under_it() {
+        let paths = resolve_with(Some("/sandbox".into()), Some("/xdg".into()), || None).unwrap();
+        assert_eq!(paths, Paths::new("/sandbox"));
+        assert_eq!(
+            paths.opencode_skills_dir(),
+            PathBuf::from("/sandbox/.config/opencode/skills")
+        );
+
+        let err = resolve_with(None, None, || None).unwrap_err();
+        assert_eq!(err.code(), "io_error");
+        assert!(crate::error::error_chain(&err).contains(HOME_ENV));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::types::Skill;
+use crate::types::{Skill, SkillFilter};
 use anyhow::Result;
 use std::path::Path;
 
@@ -7,7 +7,46 @@ pub trait SkillProvider: Send + Sync {
     /// Discover skills from the provider
     fn discover_skills(&self, url: &str, subpath: Option<&str>) -> Result<Vec<Skill>>;
 
-    /// Fetch skill content to a local directory
+    /// Discover skills at a branch, tag or commit, or the default branch when `ref_`
+    /// is `None`.
+    ///
+    /// Required so no provider silently returns skills from another revision than the
+    /// one a source or lock entry pins.
+    fn discover_skills_at(
+        &self,
+        url: &str,
+        subpath: Option<&str>,
+        ref_: Option<&str>,
+    ) -> Result<Vec<Skill>>;
+
+    /// Discover the skills `filter` selects at a branch, tag or commit.
+    ///
+    /// Providers that can list skills before fetching their content should override
+    /// this to skip unselected skills; the default discovers every skill and filters
+    /// afterwards.
+    fn discover_skills_matching(
+        &self,
+        url: &str,
+        subpath: Option<&str>,
+        ref_: Option<&str>,
+        filter: Option<&SkillFilter>,
+    ) -> Result<Vec<Skill>> {
+        let mut skills = self.discover_skills_at(url, subpath, ref_)?;
+        if let Some(filter) = filter {
+            skills.retain(|skill| filter.matches(&skill.name));
+        }
+        Ok(skills)
+    }
+
+    /// Commit `ref_` (or the default branch) currently resolves to, if the provider knows
+    fn resolve_commit(&self, _url: &str, _ref_: Option<&str>) -> Result<Option<String>> {
+        Ok(None)
+    }
+
+    /// Fetch skill content to a local directory.
+    ///
+    /// Locate the skill by `skill.path`: discovery may have renamed a duplicate, so
+    /// `skill.name` can differ from the name in the source.
     fn fetch_skill(&self, skill: &Skill, dest: &Path) -> Result<()>;
 
     /// Get folder hash for a skill (for lock file)
@@ -18,6 +57,7 @@ pub trait SkillProvider: Send + Sync {
 pub struct MockProvider {
     skills: Vec<Skill>,
     folder_hash: String,
+    commit: Option<String>,
 }
 
 impl MockProvider {
@@ -25,6 +65,7 @@ impl MockProvider {
         Self {
             skills,
             folder_hash: "mock-hash-123".to_string(),
+            commit: None,
         }
     }
 
@@ -32,6 +73,11 @@ impl MockProvider {
         self.folder_hash = hash;
         self
     }
+
+    pub fn with_commit(mut self, commit: String) -> Self {
+        self.commit = Some(commit);
+        self
+    }
 }
 
 impl SkillProvider for MockProvider {
@@ -39,6 +85,19 @@ impl SkillProvider for MockProvider {
         Ok(self.skills.clone())
     }
 
+    fn discover_skills_at(
+        &self,
+        url: &str,
+        subpath: Option<&str>,
+        _ref: Option<&str>,
+    ) -> Result<Vec<Skill>> {
+        self.discover_skills(url, subpath)
+    }
+
+    fn resolve_commit(&self, _url: &str, _ref: Option<&str>) -> Result<Option<String>> {
+        Ok(self.commit.clone())
+    }
+
     fn fetch_skill(&self, skill: &Skill, dest: &Path) -> Result<()> {
         std::fs::create_dir_all(dest)?;
         std::fs::write(dest.join("SKILL.md"), &skill.raw_content)?;
@@ -0,0 +1,282 @@
+use crate::error::Result;
+use crate::installer::InstallConfig;
+use crate::lock::LockManager;
+use crate::paths::Paths;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Environment variable naming a custom root to install into instead of the project
+pub const ROOT_ENV: &str = "AGENT_SKILLS_ROOT";
+
+/// Kind of location skills are installed in, in order of precedence
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, JsonSchema,
+)]
+#[serde(rename_all = "kebab-case")]
+pub enum ScopeKind {
+    /// An explicitly chosen root (`--root` or `AGENT_SKILLS_ROOT`)
+    Custom,
+    /// The current project
+    Project,
+    /// The user's home directory (see [`Paths`])
+    Global,
+}
+
+impl ScopeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ScopeKind::Custom => "custom",
+            ScopeKind::Project => "project",
+            ScopeKind::Global => "global",
+        }
+    }
+}
+
+/// A directory whose `.agents` holds installed skills and their lock file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scope {
+    pub kind: ScopeKind,
+    pub root: PathBuf,
+    /// Per-user paths the global scope was resolved from
+    pub paths: Option<Paths>,
+}
+
+impl Scope {
+    /// Scope of the project rooted at `root`
+    pub fn project(root: impl Into<PathBuf>) -> Self {
+        Self {
+            kind: ScopeKind::Project,
+            root: root.into(),
+            paths: None,
+        }
+    }
+
+    /// Scope of the home directory `paths` resolve to
+    pub fn global(paths: &Paths) -> Self {
+        Self {
+            kind: ScopeKind::Global,
+            root: paths.home.clone(),
+            paths: Some(paths.clone()),
+        }
+    }
+
+    /// Scope rooted at an explicitly chosen directory, laid out like a project
+    pub fn custom(root: impl Into<PathBuf>) -> Self {
+        Self {
+            kind: ScopeKind::Custom,
+            root: root.into(),
+            paths: None,
+        }
+    }
+
+    pub fn is_global(&self) -> bool {
+        self.kind == ScopeKind::Global
+    }
+
+    /// Directory skills are installed into
+    pub fn canonical_dir(&self) -> PathBuf {
+        self.root.join(".agents/skills")
+    }
+
+    /// Lock file recording the scope's installed skills
+    pub fn lock_path(&self) -> PathBuf {
+        self.root.join(".agents/.skill-lock.json")
+    }
+
+    /// Install configuration for the scope: absolute links globally, relative ones
+    /// elsewhere so the directory can be moved or committed
+    pub fn install_config(&self) -> InstallConfig {
+        if self.is_global() {
+            InstallConfig::new(self.canonical_dir())
+        } else {
+            InstallConfig::for_project(self.canonical_dir())
+        }
+    }
+}
+
+/// One scope of a [`CombinedView`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScopeSummary {
+    pub scope: ScopeKind,
+    pub root: String,
+    pub lock_path: String,
+    /// Number of skills recorded in the scope's lock file
+    pub skills: usize,
+}
+
+/// A skill recorded in one scope's lock file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScopedSkill {
+    pub name: String,
+    pub scope: ScopeKind,
+    pub path: String,
+    pub source_type: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    /// Scope whose skill of the same name takes precedence over this one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub shadowed_by: Option<ScopeKind>,
+}
+
+/// Skills of several scopes together, as an agent that loads all of them sees them
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CombinedView {
+    pub scopes: Vec<ScopeSummary>,
+    /// Sorted by name, then by scope precedence
+    pub skills: Vec<ScopedSkill>,
+}
+
+impl CombinedView {
+    /// Skills that take effect, i.e. aren't shadowed by another scope
+    pub fn effective(&self) -> impl Iterator<Item = &ScopedSkill> {
+        self.skills.iter().filter(|s| s.shadowed_by.is_none())
+    }
+}
+
+/// List the skills of every scope's lock file and flag those shadowed by a scope of
+/// higher precedence ([`ScopeKind`] order).
+///
+/// Scopes sharing a root (e.g. a project opened in the home directory) are listed once,
+/// under the kind with the highest precedence. Roots are compared after resolving
+/// symlinks and relative paths.
+pub fn combined_view(scopes: &[Scope]) -> Result<CombinedView> {
+    let mut scopes = scopes.to_vec();
+    scopes.sort_by_key(|scope| scope.kind);
+    let mut roots = Vec::new();
+    scopes.retain(|scope| {
+        let root = fs::canonicalize(&scope.root).unwrap_or_else(|_| scope.root.clone());
+        let seen = roots.contains(&root);
+        roots.push(root);
+        !seen
+    });
+
+    let mut view = CombinedView::default();
+    let mut by_name: BTreeMap<String, Vec<ScopedSkill>> = BTreeMap::new();
+    for scope in &scopes {
+        let lock = LockManager::new(scope.lock_path()).load()?;
+        view.scopes.push(ScopeSummary {
+            scope: scope.kind,
+            root: display(&scope.root),
+            lock_path: display(&scope.lock_path()),
+            skills: lock.skills.len(),
+        });
+        for (name, entry) in lock.skills {
+            let skills = by_name.entry(name.clone()).or_default();
+            let shadowed_by = skills.first().map(|winner| winner.scope);
+            skills.push(ScopedSkill {
+                name,
+                scope: scope.kind,
+                path: entry.skill_path,
+                source_type: entry.source_type,
+                source_url: entry.source_url,
+                shadowed_by,
+            });
+        }
+    }
+
+    view.skills = by_name.into_values().flatten().collect();
+    Ok(view)
+}
+
+fn display(path: &Path) -> String {
+    path.display().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Source, SourceType};
+    use tempfile::TempDir;
+
+    fn record(scope: &Scope, name: &str) {
+        let skill_dir = scope.canonical_dir().join(name);
+        fs::create_dir_all(&skill_dir).unwrap();
+        fs::write(skill_dir.join("SKILL.md"), name).unwrap();
+        let source = Source {
+            source_type: SourceType::Self_,
+            url: None,
+            subpath: None,
+            skill_filter: None,
+            ref_: None,
+        };
+        LockManager::new(scope.lock_path())
+            .update_entry(name, &source, &skill_dir)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_scope_paths() {
+        let scope = Scope::project("/p");
+        assert_eq!(scope.canonical_dir(), PathBuf::from("/p/.agents/skills"));
+        assert_eq!(
+            scope.lock_path(),
+            PathBuf::from("/p/.agents/.skill-lock.json")
+        );
+        assert!(!scope.is_global());
+        let global = Scope::global(&Paths::new("/home/u"));
+        assert!(global.is_global());
+        assert_eq!(global.lock_path(), Paths::new("/home/u").lock_path());
+    }
+
+    #[test]
+    fn test_combined_view_flags_shadowed_skills() {
+        let temp_dir = TempDir::new().unwrap();
+        let global = Scope::global(&Paths::new(temp_dir.path().join("home")));
+        let project = Scope::project(temp_dir.path().join("project"));
+        record(&global, "pdf");
+        record(&global, "docx");
+        record(&project, "pdf");
+
+        // Precedence follows the scope kind, not the order given
+        let view = combined_view(&[global, project]).unwrap();
+        assert_eq!(view.scopes.len(), 2);
+        assert_eq!(view.scopes[0].scope, ScopeKind::Project);
+        assert_eq!(view.scopes[1].skills, 2);
+
+        let flags: Vec<_> = view
+            .skills
+            .iter()
+            .map(|s| (s.name.as_str(), s.scope, s.shadowed_by))
+            .collect();
+        assert_eq!(
+            flags,
+            vec![
+                ("docx", ScopeKind::Global, None),
+                ("pdf", ScopeKind::Project, None),
+                ("pdf", ScopeKind::Global, Some(ScopeKind::Project)),
+            ]
+        );
+        assert_eq!(view.effective().count(), 2);
+    }
+
+    #[test]
+    fn test_combined_view_lists_shared_root_once() {
+        let temp_dir = TempDir::new().unwrap();
+        let global = Scope::global(&Paths::new(temp_dir.path()));
+        record(&global, "pdf");
+
+        let view = combined_view(&[global, Scope::project(temp_dir.path())]).unwrap();
+        assert_eq!(view.scopes.len(), 1);
+        assert_eq!(view.skills.len(), 1);
+        assert_eq!(view.skills[0].scope, ScopeKind::Project);
+        assert_eq!(view.skills[0].shadowed_by, None);
+
+        // Also when the paths are spelled differently
+        let project = Scope::project(temp_dir.path().join("sub/.."));
+        fs::create_dir_all(temp_dir.path().join("sub")).unwrap();
+        let view = combined_view(&[Scope::global(&Paths::new(temp_dir.path())), project]).unwrap();
+        assert_eq!(view.scopes.len(), 1);
+        #[cfg(unix)]
+        {
+            let link = temp_dir.path().join("link");
+            std::os::unix::fs::symlink(temp_dir.path(), &link).unwrap();
+            let global = Scope::global(&Paths::new(temp_dir.path()));
+            let view = combined_view(&[global, Scope::project(link)]).unwrap();
+            assert_eq!(view.scopes.len(), 1);
+            assert_eq!(view.skills.len(), 1);
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! Import and export of the lock file written by the vercel-labs `skills` CLI.
+//!
+//! The JS CLI keeps one entry per skill like ours, but with an integer `version`,
+//! `skillPath` pointing at the skill's `SKILL.md` inside the source repository and
+//! `source` holding the `owner/repo` shorthand. Fields only this crate records are
+//! written as extra entry fields, which the JS CLI leaves alone, and fields only the
+//! JS CLI knows are carried over from the existing file, so neither tool loses data.
+//!
+//! The JS CLI's `skillFolderHash` is a git tree SHA, which this crate can't compute, so
+//! it is left as the JS CLI wrote it and this crate's hash is kept in `contentHash`.
+
+use crate::types::{
+    validate_skill_name, LockEntry, LockFormatError, PreviousSource, SkillLock, HASH_VERSION,
+};
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Lock file version written by the `skills` CLI
+pub const SKILLS_CLI_LOCK_VERSION: u64 = 3;
+
+/// Whether a lock file was written by the `skills` CLI (integer `version` of 3 or later)
+pub fn is_skills_cli_lock(value: &Value) -> bool {
+    value
+        .get("version")
+        .and_then(Value::as_u64)
+        .is_some_and(|version| version >= SKILLS_CLI_LOCK_VERSION)
+}
+
+#[derive(Deserialize)]
+struct SkillsCliLock {
+    version: u64,
+    #[serde(default)]
+    skills: Map<String, Value>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SkillsCliEntry {
+    source: String,
+    source_type: String,
+    #[serde(default)]
+    source_url: Option<String>,
+    #[serde(default)]
+    skill_path: Option<String>,
+    installed_at: chrono::DateTime<chrono::Utc>,
+    updated_at: chrono::DateTime<chrono::Utc>,
+    // Written by this crate only
+    #[serde(default)]
+    content_hash: String,
+    #[serde(default, rename = "ref")]
+    ref_: Option<String>,
+    #[serde(default)]
+    commit: Option<String>,
+    #[serde(default)]
+    subpath: Option<String>,
+    #[serde(default)]
+    history: Vec<PreviousSource>,
+    #[serde(default)]
+    install_path: Option<String>,
+}
+
+/// Read a `skills` CLI lock file.
+///
+/// Skills without a recorded install path are assumed to live in the `skills`
+/// directory next to the lock file, where the JS CLI puts them. Skills this crate
+/// hasn't hashed yet get an empty `skill_folder_hash`.
+pub fn import_lock(value: Value, lock_dir: &Path) -> Result<SkillLock, LockFormatError> {
+    let (lock, violations) = import_lock_lenient(value, lock_dir)?;
+    match violations.into_iter().next() {
+        Some(violation) => Err(violation),
+        None => Ok(lock),
+    }
+}
+
+/// Read a `skills` CLI lock file, skipping entries that can't be read and reporting
+/// them instead of failing (see [`SkillLock::from_value_lenient`])
+pub fn import_lock_lenient(
+    value: Value,
+    lock_dir: &Path,
+) -> Result<(SkillLock, Vec<LockFormatError>), LockFormatError> {
+    let lock: SkillsCliLock = serde_path_to_error::deserialize(value)?;
+
+    let mut violations = Vec::new();
+    let mut skills = BTreeMap::new();
+    for (name, entry) in lock.skills {
+        let field = format!("skills.{}", name);
+        if let Err(error) = validate_skill_name(&name) {
+            violations.push(LockFormatError::invalid_name(field, error));
+            continue;
+        }
+        match serde_path_to_error::deserialize::<_, SkillsCliEntry>(entry) {
+            Ok(entry) => {
+                skills.insert(name.clone(), import_entry(&name, entry, lock_dir));
+            }
+            Err(error) => violations.push(LockFormatError::at(&field, error)),
+        }
+    }
+
+    let lock = SkillLock {
+        version: lock.version.to_string(),
+        skills,
+    };
+    Ok((lock, violations))
+}
+
+fn import_entry(name: &str, entry: SkillsCliEntry, lock_dir: &Path) -> LockEntry {
+    let skill_path = entry
+        .install_path
+        .unwrap_or_else(|| default_install_path(lock_dir, name));
+    let relative_path = entry.skill_path.map(|path| {
+        path.strip_suffix("SKILL.md")
+            .map(|dir| dir.trim_end_matches('/'))
+            .filter(|dir| !dir.is_empty())
+            .unwrap_or(".")
+            .to_string()
+    });
+    LockEntry {
+        source: entry.source,
+        source_type: entry.source_type,
+        source_url: entry.source_url.filter(|url| !url.is_empty()),
+        skill_path,
+        skill_folder_hash: entry.content_hash,
+        hash_version: HASH_VERSION,
+        installed_at: entry.installed_at,
+        updated_at: entry.updated_at,
+        ref_: entry.ref_,
+        commit: entry.commit,
+        subpath: entry.subpath,
+        relative_path,
+        history: entry.history,
+    }
+}
+
+/// Write `lock` as a `skills` CLI lock file, keeping fields of `existing` (the file
+/// being replaced) that this crate doesn't model
+pub fn export_lock(lock: &SkillLock, existing: Option<&Value>, lock_dir: &Path) -> Value {
+    let mut root = existing
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+    let previous_skills = root
+        .get("skills")
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_default();
+
+    let skills: Map<String, Value> = lock
+        .skills
+        .iter()
+        .map(|(name, entry)| {
+            let previous = previous_skills.get(name).and_then(Value::as_object);
+            let default_path = default_install_path(lock_dir, name);
+            let object = export_entry(entry, previous, &default_path);
+            (name.clone(), Value::Object(object))
+        })
+        .collect();
+
+    root.insert("version".to_string(), Value::from(SKILLS_CLI_LOCK_VERSION));
+    root.insert("skills".to_string(), Value::Object(skills));
+    Value::Object(root)
+}
+
+fn export_entry(
+    entry: &LockEntry,
+    previous: Option<&Map<String, Value>>,
+    default_path: &str,
+) -> Map<String, Value> {
+    let mut object = previous.cloned().unwrap_or_default();
+
+    // Keep the JS CLI's skillPath when we don't know where in the source the skill is
+    let skill_path = entry
+        .relative_path
+        .as_deref()
+        .map(|dir| match dir {
+            "." => json!("SKILL.md"),
+            dir => json!(format!("{}/SKILL.md", dir)),
+        })
+        .or_else(|| object.get("skillPath").cloned());
+    let history = (!entry.history.is_empty()).then(|| json!(entry.history));
+    // The JS CLI's hash still describes the skill unless this crate has reinstalled it
+    let unchanged = object
+        .get("updatedAt")
+        .and_then(|value| {
+            serde_json::from_value::<chrono::DateTime<chrono::Utc>>(value.clone()).ok()
+        })
+        .is_some_and(|updated_at| updated_at == entry.updated_at);
+    let upstream_hash = object
+        .get("skillFolderHash")
+        .filter(|_| unchanged)
+        .cloned()
+        .unwrap_or_else(|| json!(""));
+    let fields = [
+        ("source", Some(json!(shorthand_source(entry)))),
+        ("sourceType", Some(json!(entry.source_type))),
+        (
+            "sourceUrl",
+            Some(json!(entry.source_url.as_deref().unwrap_or_default())),
+        ),
+        ("skillPath", skill_path),
+        ("skillFolderHash", Some(upstream_hash)),
+        ("installedAt", Some(json!(entry.installed_at))),
+        ("updatedAt", Some(json!(entry.updated_at))),
+        (
+            "installPath",
+            (entry.skill_path != default_path).then(|| json!(entry.skill_path)),
+        ),
+        (
+            "contentHash",
+            (!entry.skill_folder_hash.is_empty()).then(|| json!(entry.skill_folder_hash)),
+        ),
+        ("ref", entry.ref_.as_deref().map(Value::from)),
+        ("commit", entry.commit.as_deref().map(Value::from)),
+        ("subpath", entry.subpath.as_deref().map(Value::from)),
+        ("history", history),
+    ];
+    for (key, value) in fields {
+        match value {
+            Some(value) => {
+                object.insert(key.to_string(), value);
+            }
+            None => {
+                object.remove(key);
+            }
+        }
+    }
+    object
+}
+
+/// Where the JS CLI installs `name`: the `skills` directory next to the lock file
+fn default_install_path(lock_dir: &Path, name: &str) -> String {
+    lock_dir
+        .join("skills")
+        .join(name)
+        .to_string_lossy()
+        .to_string()
+}
+
+/// `owner/repo` for GitHub sources, as the JS CLI records them; otherwise the URL
+fn shorthand_source(entry: &LockEntry) -> String {
+    let Some(url) = entry.source_url.as_deref() else {
+        return entry.source.clone();
+    };
+    if entry.source_type == "github" {
+        if let Some(path) = url
+            .strip_prefix("https://github.com/")
+            .or_else(|| url.strip_prefix("git@github.com:"))
+        {
+            return path
+                .trim_end_matches('/')
+                .trim_end_matches(".git")
+                .to_string();
+        }
+    }
+    url.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn skills_cli_lock() -> Value {
+        json!({
+            "version": 3,
+            "skills": {
+                "pdf": {
+                    "source": "vercel-labs/agent-skills",
+                    "sourceType": "github",
+                    "sourceUrl": "https://github.com/vercel-labs/agent-skills.git",
+                    "skillPath": "skills/pdf/SKILL.md",
+                    "skillFolderHash": "4b825dc642cb6eb9a060e54bf8d69288fbee4904",
+                    "installedAt": "2025-01-01T00:00:00Z",
+                    "updatedAt": "2025-01-02T00:00:00Z",
+                    "pinned": true
+                }
+            },
+            "dismissed": { "findSkillsPrompt": true }
+        })
+    }
+
+    #[test]
+    fn test_detects_skills_cli_lock() {
+        assert!(is_skills_cli_lock(&skills_cli_lock()));
+        assert!(!is_skills_cli_lock(
+            &json!({"version": "1.0", "skills": {}})
+        ));
+        assert!(!is_skills_cli_lock(&json!({"skills": []})));
+    }
+
+    #[test]
+    fn test_import_maps_fields() {
+        let lock = import_lock(skills_cli_lock(), Path::new("/home/u/.agents")).unwrap();
+        let entry = &lock.skills["pdf"];
+        assert_eq!(entry.source, "vercel-labs/agent-skills");
+        assert_eq!(entry.source_type, "github");
+        assert_eq!(entry.relative_path.as_deref(), Some("skills/pdf"));
+        assert_eq!(entry.skill_path, "/home/u/.agents/skills/pdf");
+        // The JS CLI's git tree SHA isn't a hash of ours
+        assert_eq!(entry.skill_folder_hash, "");
+
+        let err = import_lock(
+            json!({"version": 3, "skills": {"pdf": {"source": "x"}}}),
+            Path::new("/"),
+        )
+        .unwrap_err();
+        assert_eq!(err.field, "skills.pdf");
+
+        let mut invalid_name = skills_cli_lock();
+        invalid_name["skills"]["My Skill"] = invalid_name["skills"]["pdf"].clone();
+        let err = import_lock(invalid_name, Path::new("/")).unwrap_err();
+        assert_eq!(err.field, "skills.My Skill");
+    }
+
+    #[test]
+    fn test_round_trip_keeps_unknown_fields() {
+        let original = skills_cli_lock();
+        let lock = import_lock(original.clone(), Path::new("/home/u/.agents")).unwrap();
+        let exported = export_lock(&lock, Some(&original), Path::new("/home/u/.agents"));
+
+        assert_eq!(exported["version"], 3);
+        assert_eq!(exported["dismissed"], original["dismissed"]);
+        let entry = &exported["skills"]["pdf"];
+        for field in [
+            "source",
+            "sourceType",
+            "sourceUrl",
+            "skillPath",
+            "skillFolderHash",
+            "pinned",
+        ] {
+            assert_eq!(entry[field], original["skills"]["pdf"][field], "{}", field);
+        }
+        assert!(entry.get("installPath").is_none());
+        assert_eq!(
+            import_lock(exported, Path::new("/home/u/.agents")).unwrap(),
+            lock
+        );
+    }
+
+    #[test]
+    fn test_export_records_crate_only_fields() {
+        let mut lock = import_lock(skills_cli_lock(), Path::new("/p/.agents")).unwrap();
+        let entry = lock.skills.get_mut("pdf").unwrap();
+        entry.ref_ = Some("v1".to_string());
+        entry.commit = Some("abc123".to_string());
+        entry.skill_path = "/elsewhere/pdf".to_string();
+        entry.skill_folder_hash = "sha256-of-ours".to_string();
+
+        let exported = export_lock(&lock, Some(&skills_cli_lock()), Path::new("/p/.agents"));
+        assert_eq!(exported["skills"]["pdf"]["contentHash"], "sha256-of-ours");
+        assert_eq!(
+            exported["skills"]["pdf"]["skillFolderHash"],
+            "4b825dc642cb6eb9a060e54bf8d69288fbee4904"
+        );
+
+        // Once this crate reinstalls the skill, the JS CLI's hash no longer describes it
+        let entry = lock.skills.get_mut("pdf").unwrap();
+        entry.updated_at = chrono::Utc::now();
+        let exported = export_lock(&lock, Some(&skills_cli_lock()), Path::new("/p/.agents"));
+        assert_eq!(exported["skills"]["pdf"]["skillFolderHash"], "");
+
+        let exported = export_lock(&lock, None, Path::new("/p/.agents"));
+        assert_eq!(exported["skills"]["pdf"]["ref"], "v1");
+        assert_eq!(exported["skills"]["pdf"]["commit"], "abc123");
+        assert_eq!(exported["skills"]["pdf"]["installPath"], "/elsewhere/pdf");
+        assert!(exported.get("dismissed").is_none());
+        assert_eq!(
+            import_lock(exported, Path::new("/p/.agents")).unwrap(),
+            lock
+        );
+    }
+}
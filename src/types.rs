@@ -1,20 +1,19 @@
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 
 fn deserialize_lock_version<'de, D>(deserializer: D) -> Result<String, D::Error>
 where
     D: serde::Deserializer<'de>,
 {
-    #[derive(Deserialize)]
-    #[serde(untagged)]
-    enum LockVersion {
-        String(String),
-        Integer(i64),
-    }
-
-    match LockVersion::deserialize(deserializer)? {
-        LockVersion::String(version) => Ok(version),
-        LockVersion::Integer(version) => Ok(version.to_string()),
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(version) => Ok(version),
+        serde_json::Value::Number(version) if version.is_i64() => Ok(version.to_string()),
+        other => Err(serde::de::Error::custom(format!(
+            "expected a string or integer version, found {}",
+            other
+        ))),
     }
 }
 
@@ -24,56 +23,156 @@ struct LegacyLockEntry {
     name: String,
     path: String,
     source_type: String,
+    #[serde(default, alias = "installedAt")]
+    installed_at: Option<chrono::DateTime<chrono::Utc>>,
+    #[serde(default, alias = "updatedAt")]
+    updated_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
-/// Helper enum for deserializing both old and new lock file formats
-#[derive(Deserialize)]
+/// Schema of the lock file `version`, which is written as a string but read as either
+#[derive(JsonSchema)]
 #[serde(untagged)]
-enum SkillLockFormat {
-    Legacy {
-        skills: Vec<LegacyLockEntry>,
-    },
-    New {
-        #[serde(deserialize_with = "deserialize_lock_version")]
-        version: String,
-        #[serde(default)]
-        skills: HashMap<String, LockEntry>,
-    },
+#[allow(dead_code)]
+enum LockVersion {
+    String(String),
+    Integer(i64),
 }
 
-impl From<SkillLockFormat> for SkillLock {
-    fn from(format: SkillLockFormat) -> Self {
-        match format {
-            SkillLockFormat::New { version, skills } => SkillLock { version, skills },
-            SkillLockFormat::Legacy { skills } => {
-                let now = chrono::Utc::now();
-                let mut skill_map = HashMap::new();
-
-                for legacy_entry in skills {
-                    // Skip entries with empty paths (not actually installed)
-                    if legacy_entry.path.is_empty() {
-                        continue;
-                    }
-
-                    let entry = LockEntry {
-                        source: legacy_entry.source_type.clone(),
-                        source_type: legacy_entry.source_type,
-                        source_url: None,
-                        skill_path: legacy_entry.path,
-                        skill_folder_hash: String::new(), // Will be computed on next update
-                        installed_at: now,
-                        updated_at: now,
-                    };
-                    skill_map.insert(legacy_entry.name, entry);
-                }
+impl SkillLock {
+    fn from_legacy(entries: Vec<LegacyLockEntry>) -> Self {
+        let now = chrono::Utc::now();
+        let mut skill_map = BTreeMap::new();
+
+        for legacy_entry in entries {
+            // Skip entries with empty paths (not actually installed)
+            if legacy_entry.path.is_empty() {
+                continue;
+            }
 
-                SkillLock {
-                    version: "1.0".to_string(),
-                    skills: skill_map,
+            let installed_at = legacy_entry.installed_at.unwrap_or(now);
+            let entry = LockEntry {
+                source: legacy_entry.source_type.clone(),
+                source_type: legacy_entry.source_type,
+                source_url: None,
+                skill_path: legacy_entry.path,
+                skill_folder_hash: String::new(), // Computed by `LockManager` when saving
+                hash_version: HASH_VERSION,
+                installed_at,
+                updated_at: legacy_entry.updated_at.unwrap_or(installed_at),
+                ref_: None,
+                commit: None,
+                subpath: None,
+                relative_path: None,
+                history: Vec::new(),
+            };
+            skill_map.insert(legacy_entry.name, entry);
+        }
+
+        SkillLock {
+            version: LOCK_VERSION.to_string(),
+            skills: skill_map,
+        }
+    }
+}
+
+/// Where and why a lock file doesn't follow the lock format
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{field}: {reason}")]
+pub struct LockFormatError {
+    /// JSON path of the offending value, e.g. `skills.pdf.sourceType`
+    pub field: String,
+    pub reason: String,
+    /// Whether the value is an invalid skill name, whose entry is left out even by
+    /// strict loading so the rest of the lock stays usable
+    pub invalid_name: bool,
+}
+
+impl From<serde_path_to_error::Error<serde_json::Error>> for LockFormatError {
+    fn from(error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        LockFormatError::at("(root)", error)
+    }
+}
+
+impl LockFormatError {
+    /// Error for a value parsed from the JSON path `prefix`
+    pub(crate) fn at(prefix: &str, error: serde_path_to_error::Error<serde_json::Error>) -> Self {
+        let field = match error.path().to_string() {
+            path if path == "." => prefix.to_string(),
+            path if prefix == "(root)" => path,
+            path => format!("{}.{}", prefix, path),
+        };
+        LockFormatError {
+            field,
+            reason: error.into_inner().to_string(),
+            invalid_name: false,
+        }
+    }
+
+    pub(crate) fn unknown_field(field: String) -> Self {
+        let key = field.rsplit('.').next().unwrap_or_default().to_string();
+        LockFormatError {
+            field,
+            reason: format!("unknown field `{}`", key),
+            invalid_name: false,
+        }
+    }
+
+    pub(crate) fn invalid_name(field: String, error: InvalidSkillName) -> Self {
+        LockFormatError {
+            field,
+            reason: error.reason.to_string(),
+            invalid_name: true,
+        }
+    }
+}
+
+/// Property names of `T`'s JSON schema, i.e. the fields the lock format allows
+pub(crate) fn schema_fields<T: JsonSchema>() -> BTreeSet<String> {
+    schemars::schema_for!(T)
+        .schema
+        .object
+        .map(|object| object.properties.keys().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Parse one lock entry at `field`, dropping fields the format doesn't know and
+/// reporting them in `violations`
+fn parse_lock_entry(
+    field: &str,
+    mut value: Value,
+    violations: &mut Vec<LockFormatError>,
+) -> Result<LockEntry, LockFormatError> {
+    if let Some(entry) = value.as_object_mut() {
+        strip_unknown_fields(entry, &schema_fields::<LockEntry>(), field, violations);
+        if let Some(Value::Array(history)) = entry.get_mut("history") {
+            let known = schema_fields::<PreviousSource>();
+            for (i, previous) in history.iter_mut().enumerate() {
+                if let Some(previous) = previous.as_object_mut() {
+                    let prefix = format!("{}.history[{}]", field, i);
+                    strip_unknown_fields(previous, &known, &prefix, violations);
                 }
             }
         }
     }
+    serde_path_to_error::deserialize(value).map_err(|error| LockFormatError::at(field, error))
+}
+
+fn strip_unknown_fields(
+    object: &mut Map<String, Value>,
+    known: &BTreeSet<String>,
+    prefix: &str,
+    violations: &mut Vec<LockFormatError>,
+) {
+    object.retain(|key, _| {
+        let keep = known.contains(key);
+        if !keep {
+            violations.push(LockFormatError::unknown_field(format!(
+                "{}.{}",
+                prefix, key
+            )));
+        }
+        keep
+    });
 }
 
 /// Represents a skill definition
@@ -92,6 +191,52 @@ pub struct Skill {
     pub auxiliary_files: HashMap<String, String>,
 }
 
+/// Maximum length of a skill name
+pub const MAX_SKILL_NAME_LEN: usize = 64;
+
+/// Error returned when a skill name doesn't follow the naming rules
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("Invalid skill name {name:?}: {reason}")]
+pub struct InvalidSkillName {
+    pub name: String,
+    pub reason: &'static str,
+}
+
+/// Validate a skill name before it is used as a directory name.
+///
+/// Names must be 1-64 characters of lowercase ASCII letters, digits and single
+/// hyphens, and must not start or end with a hyphen. This keeps names safe to
+/// join onto install directories (no separators, `..`, or absolute paths).
+pub fn validate_skill_name(name: &str) -> Result<(), InvalidSkillName> {
+    let invalid = |reason| {
+        Err(InvalidSkillName {
+            name: name.to_string(),
+            reason,
+        })
+    };
+
+    if name.is_empty() {
+        return invalid("must not be empty");
+    }
+    if name.len() > MAX_SKILL_NAME_LEN {
+        return invalid("must be at most 64 characters");
+    }
+    if !name
+        .chars()
+        .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-')
+    {
+        return invalid("may only contain lowercase letters, digits and hyphens");
+    }
+    if name.starts_with('-') || name.ends_with('-') {
+        return invalid("must not start or end with a hyphen");
+    }
+    if name.contains("--") {
+        return invalid("must not contain consecutive hyphens");
+    }
+
+    Ok(())
+}
+
 /// Metadata for a skill
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq)]
 pub struct SkillMetadata {
@@ -118,6 +263,11 @@ impl SourceType {
     pub fn is_embedded(&self) -> bool {
         matches!(self, SourceType::Self_)
     }
+
+    /// Whether skills from this source are fetched through a `SkillProvider`
+    pub fn requires_provider(&self) -> bool {
+        !matches!(self, SourceType::Local | SourceType::Self_)
+    }
 }
 
 /// Source specification for skill installation
@@ -129,14 +279,138 @@ pub struct Source {
     pub url: Option<String>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub subpath: Option<String>,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub skill_filter: Option<String>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "deserialize_skill_filter"
+    )]
+    pub skill_filter: Option<SkillFilter>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub ref_: Option<String>,
 }
 
+/// Selects skills by name.
+///
+/// Patterns may use `*` (any run of characters) and `?` (any single character).
+/// A skill is selected when it matches an include pattern (or none are given)
+/// and matches no exclude pattern.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct SkillFilter {
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub exclude: Vec<String>,
+}
+
+impl SkillFilter {
+    /// Build a filter from CLI-style values, each of which may be a comma-separated list
+    pub fn new<S: AsRef<str>>(include: &[S], exclude: &[S]) -> Self {
+        Self {
+            include: split_patterns(include),
+            exclude: split_patterns(exclude),
+        }
+    }
+
+    /// Whether the filter selects every skill
+    pub fn is_empty(&self) -> bool {
+        self.include.is_empty() && self.exclude.is_empty()
+    }
+
+    /// Check whether a skill name is selected by the filter
+    pub fn matches(&self, name: &str) -> bool {
+        let included = self.include.is_empty() || self.include.iter().any(|p| glob_match(p, name));
+        included && !self.exclude.iter().any(|p| glob_match(p, name))
+    }
+}
+
+/// Read `skill_filter` as a [`SkillFilter`] or, as it was written before it had excludes,
+/// a string of comma-separated skill names or patterns
+fn deserialize_skill_filter<'de, D>(deserializer: D) -> Result<Option<SkillFilter>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum AnyFilter {
+        Patterns(String),
+        Filter(SkillFilter),
+    }
+
+    Ok(
+        Option::<AnyFilter>::deserialize(deserializer)?.map(|filter| match filter {
+            AnyFilter::Patterns(patterns) => SkillFilter::new(&[patterns], &[]),
+            AnyFilter::Filter(filter) => filter,
+        }),
+    )
+}
+
+impl std::fmt::Display for SkillFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.include.is_empty() {
+            write!(f, "*")?;
+        } else {
+            write!(f, "{}", self.include.join(", "))?;
+        }
+        if !self.exclude.is_empty() {
+            write!(f, " (excluding {})", self.exclude.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn split_patterns<S: AsRef<str>>(values: &[S]) -> Vec<String> {
+    values
+        .iter()
+        .flat_map(|v| v.as_ref().split(','))
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Match `text` against a pattern with `*` and `?` wildcards
+fn glob_match(pattern: &str, text: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let text: Vec<char> = text.chars().collect();
+    let (mut p, mut t) = (0, 0);
+    // Position of the last `*` and the text index it was tried against
+    let mut backtrack: Option<(usize, usize)> = None;
+
+    while t < text.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                backtrack = Some((p, t));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == text[t] => {
+                p += 1;
+                t += 1;
+            }
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    t = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Version of the scheme [`LockEntry::skill_folder_hash`] is computed with: 1 hashed only
+/// `SKILL.md`, 2 hashes every file of the skill directory
+pub const HASH_VERSION: u32 = 2;
+
+/// Entries written before the hash was versioned hashed only `SKILL.md`
+fn unversioned_hash() -> u32 {
+    1
+}
+
 /// Lock entry for installed skills
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
 pub struct LockEntry {
     pub source: String,
     #[serde(rename = "sourceType")]
@@ -147,17 +421,74 @@ pub struct LockEntry {
     pub skill_path: String,
     #[serde(rename = "skillFolderHash")]
     pub skill_folder_hash: String,
+    /// [`HASH_VERSION`] `skill_folder_hash` was computed with
+    #[serde(rename = "hashVersion", default = "unversioned_hash")]
+    pub hash_version: u32,
     #[serde(rename = "installedAt")]
     pub installed_at: chrono::DateTime<chrono::Utc>,
     #[serde(rename = "updatedAt")]
     pub updated_at: chrono::DateTime<chrono::Utc>,
+    /// Branch, tag or commit requested when the skill was installed
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub ref_: Option<String>,
+    /// Commit the ref resolved to, as reported by the provider
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    /// Directory within the source that was searched for skills
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subpath: Option<String>,
+    /// Skill directory relative to the source root, e.g. `skills/pdf`
+    #[serde(
+        rename = "relativePath",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub relative_path: Option<String>,
+    /// Sources the skill was installed from before, oldest first
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<PreviousSource>,
 }
 
+/// Source a skill was installed from before being reinstalled from a different one
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct PreviousSource {
+    pub source: String,
+    #[serde(rename = "sourceType")]
+    pub source_type: String,
+    #[serde(rename = "sourceUrl", skip_serializing_if = "Option::is_none")]
+    pub source_url: Option<String>,
+    #[serde(rename = "ref", default, skip_serializing_if = "Option::is_none")]
+    pub ref_: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub subpath: Option<String>,
+    /// When the skill was reinstalled from the new source
+    #[serde(rename = "replacedAt")]
+    pub replaced_at: chrono::DateTime<chrono::Utc>,
+}
+
+impl std::fmt::Display for PreviousSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.source_url {
+            Some(url) => write!(f, "{} {}", self.source_type, url),
+            None => write!(f, "{}", self.source_type),
+        }
+    }
+}
+
+/// Lock file `version` written in this crate's format
+pub const LOCK_VERSION: &str = "1.0";
+
 /// Lock file structure
-#[derive(Debug, Clone, Serialize, Default, PartialEq)]
+#[derive(Debug, Clone, Serialize, Default, PartialEq, JsonSchema)]
+#[schemars(deny_unknown_fields)]
 pub struct SkillLock {
+    #[schemars(with = "LockVersion")]
     pub version: String,
-    pub skills: HashMap<String, LockEntry>,
+    /// Entries keyed by skill name, kept sorted so the file diffs cleanly
+    pub skills: BTreeMap<String, LockEntry>,
 }
 
 impl<'de> serde::Deserialize<'de> for SkillLock {
@@ -165,16 +496,113 @@ impl<'de> serde::Deserialize<'de> for SkillLock {
     where
         D: serde::Deserializer<'de>,
     {
-        let format = SkillLockFormat::deserialize(deserializer)?;
-        Ok(format.into())
+        let value = serde_json::Value::deserialize(deserializer)?;
+        SkillLock::from_value(value).map_err(serde::de::Error::custom)
     }
 }
 
 impl SkillLock {
+    /// Whether a lock file uses the legacy format, whose `skills` is a list
+    pub fn is_legacy(value: &serde_json::Value) -> bool {
+        value.get("skills").is_some_and(serde_json::Value::is_array)
+    }
+
+    /// Parse a lock file in the current or legacy format, reporting the path of the
+    /// first value that doesn't match
+    pub fn from_value(value: serde_json::Value) -> Result<Self, LockFormatError> {
+        let (lock, violations) = Self::from_value_lenient(value)?;
+        match violations.into_iter().next() {
+            Some(violation) => Err(violation),
+            None => Ok(lock),
+        }
+    }
+
+    /// Parse a lock file, leaving out what doesn't follow the format instead of failing.
+    ///
+    /// Unknown fields are dropped and entries that can't be read (or whose name is
+    /// invalid) are skipped; each is reported in the returned list. Only a lock whose
+    /// `version` or `skills` can't be read at all is an error.
+    pub fn from_value_lenient(
+        value: serde_json::Value,
+    ) -> Result<(Self, Vec<LockFormatError>), LockFormatError> {
+        let mut violations = Vec::new();
+
+        if Self::is_legacy(&value) {
+            let Some(Value::Array(items)) = value.get("skills") else {
+                unreachable!("legacy lock files have a skills list");
+            };
+            let mut entries = Vec::new();
+            for (i, item) in items.iter().enumerate() {
+                let field = format!("skills[{}]", i);
+                match serde_path_to_error::deserialize::<_, LegacyLockEntry>(item) {
+                    Ok(entry) => match validate_skill_name(&entry.name) {
+                        Ok(()) => entries.push(entry),
+                        Err(error) => violations.push(LockFormatError::invalid_name(
+                            format!("{}.name", field),
+                            error,
+                        )),
+                    },
+                    Err(error) => violations.push(LockFormatError::at(&field, error)),
+                }
+            }
+            return Ok((Self::from_legacy(entries), violations));
+        }
+
+        let Value::Object(mut root) = value else {
+            return Err(LockFormatError {
+                field: "(root)".to_string(),
+                reason: format!("expected an object, found {}", value),
+                invalid_name: false,
+            });
+        };
+        let version = root.remove("version").ok_or_else(|| LockFormatError {
+            field: "version".to_string(),
+            reason: "missing field `version`".to_string(),
+            invalid_name: false,
+        })?;
+        let version = deserialize_lock_version(version).map_err(|error| LockFormatError {
+            field: "version".to_string(),
+            reason: error.to_string(),
+            invalid_name: false,
+        })?;
+        let entries = match root.remove("skills") {
+            None => Map::new(),
+            Some(Value::Object(entries)) => entries,
+            Some(other) => {
+                return Err(LockFormatError {
+                    field: "skills".to_string(),
+                    reason: format!("expected a map of skills, found {}", other),
+                    invalid_name: false,
+                })
+            }
+        };
+        violations.extend(
+            root.into_iter()
+                .map(|(key, _)| LockFormatError::unknown_field(key)),
+        );
+
+        let mut skills = BTreeMap::new();
+        for (name, value) in entries {
+            let field = format!("skills.{}", name);
+            if let Err(error) = validate_skill_name(&name) {
+                violations.push(LockFormatError::invalid_name(field, error));
+                continue;
+            }
+            match parse_lock_entry(&field, value, &mut violations) {
+                Ok(entry) => {
+                    skills.insert(name, entry);
+                }
+                Err(violation) => violations.push(violation),
+            }
+        }
+
+        Ok((SkillLock { version, skills }, violations))
+    }
+
     pub fn new() -> Self {
         Self {
-            version: "1.0".to_string(),
-            skills: HashMap::new(),
+            version: LOCK_VERSION.to_string(),
+            skills: BTreeMap::new(),
         }
     }
 }
@@ -203,6 +631,36 @@ mod tests {
         assert!(!SourceType::Local.is_embedded());
     }
 
+    #[test]
+    fn test_validate_skill_name_accepts_valid_names() {
+        assert!(validate_skill_name("test-skill").is_ok());
+        assert!(validate_skill_name("pdf").is_ok());
+        assert!(validate_skill_name("skill-2").is_ok());
+        assert!(validate_skill_name(&"a".repeat(MAX_SKILL_NAME_LEN)).is_ok());
+    }
+
+    #[test]
+    fn test_validate_skill_name_rejects_invalid_names() {
+        for name in [
+            "",
+            "../../.ssh",
+            "/tmp/x",
+            "..",
+            "a/b",
+            "a\\b",
+            "Test-Skill",
+            "my_skill",
+            "-skill",
+            "skill-",
+            "my--skill",
+            "skill name",
+        ] {
+            let err = validate_skill_name(name).unwrap_err();
+            assert_eq!(err.name, name);
+        }
+        assert!(validate_skill_name(&"a".repeat(MAX_SKILL_NAME_LEN + 1)).is_err());
+    }
+
     #[test]
     fn test_skill_serialization() {
         let skill = Skill {
@@ -227,8 +685,14 @@ mod tests {
             source_url: None,
             skill_path: "/path/to/skill".to_string(),
             skill_folder_hash: "abc123".to_string(),
+            hash_version: HASH_VERSION,
             installed_at: chrono::Utc::now(),
             updated_at: chrono::Utc::now(),
+            ref_: None,
+            commit: None,
+            subpath: None,
+            relative_path: None,
+            history: Vec::new(),
         };
 
         let json = serde_json::to_string(&entry).unwrap();
@@ -287,13 +751,46 @@ mod tests {
         assert_eq!(entry1.source, "github");
         assert_eq!(entry1.source_type, "github");
         assert_eq!(entry1.skill_path, "/path/to/skill1");
-        assert_eq!(entry1.skill_folder_hash, ""); // Will be computed on next update
+        assert_eq!(entry1.skill_folder_hash, ""); // Computed when the lock is saved or by `lock doctor`
 
         let entry3 = lock.skills.get("test-skill-3").unwrap();
         assert_eq!(entry3.source, "self");
         assert_eq!(entry3.source_type, "self");
     }
 
+    #[test]
+    fn test_invalid_lock_reports_field_path() {
+        let error = |json: &str| {
+            SkillLock::from_value(serde_json::from_str(json).unwrap())
+                .unwrap_err()
+                .field
+        };
+
+        assert_eq!(
+            error(r#"{"version": "1.0", "skills": {"pdf": {"source": "github"}}}"#),
+            "skills.pdf"
+        );
+        assert_eq!(
+            error(
+                r#"{"version": "1.0", "skills": {"pdf": {
+                    "source": "github", "sourceType": 3, "skillPath": "/p",
+                    "skillFolderHash": "", "installedAt": "2024-01-01T00:00:00Z",
+                    "updatedAt": "2024-01-01T00:00:00Z"}}}"#
+            ),
+            "skills.pdf.sourceType"
+        );
+        assert_eq!(error(r#"{"version": true}"#), "version");
+        assert_eq!(error(r#"{"version": "1.0", "extra": 1}"#), "extra");
+        assert_eq!(
+            error(r#"{"version": "1.0", "skills": {"../x": {}}}"#),
+            "skills.../x"
+        );
+        assert_eq!(
+            error(r#"{"skills": [{"name": "a", "path": "/p"}]}"#),
+            "skills[0]"
+        );
+    }
+
     #[test]
     fn test_new_lock_format_still_works() {
         // Ensure new format continues to work
@@ -320,4 +817,61 @@ mod tests {
         let entry = lock.skills.get("test-skill").unwrap();
         assert_eq!(entry.skill_folder_hash, "abc123");
     }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("pdf-*", "pdf-tools"));
+        assert!(glob_match("pdf-*", "pdf-"));
+        assert!(!glob_match("pdf-*", "docx"));
+        assert!(glob_match("*-tools", "pdf-tools"));
+        assert!(glob_match("p?f", "pdf"));
+        assert!(glob_match("*a*b*", "xaxxbx"));
+        assert!(!glob_match("*a*b", "xaxxbx"));
+        assert!(glob_match("docx", "docx"));
+        assert!(!glob_match("docx", "docx2"));
+    }
+
+    #[test]
+    fn test_skill_filter_include_and_exclude() {
+        let filter = SkillFilter::new(&["pdf-*,docx", " xlsx "], &["pdf-old"]);
+        assert_eq!(filter.include, vec!["pdf-*", "docx", "xlsx"]);
+        assert_eq!(filter.exclude, vec!["pdf-old"]);
+
+        assert!(filter.matches("pdf-tools"));
+        assert!(filter.matches("docx"));
+        assert!(filter.matches("xlsx"));
+        assert!(!filter.matches("pdf-old"));
+        assert!(!filter.matches("pptx"));
+        assert_eq!(filter.to_string(), "pdf-*, docx, xlsx (excluding pdf-old)");
+
+        let exclude_only = SkillFilter::new(&[] as &[&str], &["internal-*"]);
+        assert!(exclude_only.matches("pdf"));
+        assert!(!exclude_only.matches("internal-tools"));
+        assert!(SkillFilter::default().is_empty());
+    }
+
+    #[test]
+    fn test_source_skill_filter_accepts_string() {
+        let source: Source =
+            serde_json::from_str(r#"{"type": "local", "url": ".", "skill_filter": "pdf,docx"}"#)
+                .unwrap();
+        assert_eq!(
+            source.skill_filter,
+            Some(SkillFilter::new(&["pdf", "docx"], &[]))
+        );
+
+        let filter = SkillFilter::new(&["pdf-*"], &["pdf-old"]);
+        let source = Source {
+            source_type: SourceType::Local,
+            url: Some(".".to_string()),
+            subpath: None,
+            skill_filter: Some(filter),
+            ref_: None,
+        };
+        let json = serde_json::to_string(&source).unwrap();
+        assert_eq!(serde_json::from_str::<Source>(&json).unwrap(), source);
+
+        let source: Source = serde_json::from_str(r#"{"type": "self"}"#).unwrap();
+        assert_eq!(source.skill_filter, None);
+    }
 }